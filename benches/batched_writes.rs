@@ -0,0 +1,67 @@
+//! Measures the save path with and without batched writes.
+//!
+//! Criterion isn't a dependency, so this is a plain `harness = false` bench timed with
+//! `std::time`. Run it with `cargo bench --bench batched_writes`.
+
+use bevy_app::prelude::*;
+use bevy_easy_database::*;
+use bevy_ecs::prelude::*;
+use std::time::{Duration, Instant};
+
+const ENTITIES: u32 = 1_000;
+const FRAMES: u32 = 100;
+
+#[derive(Component, serde::Serialize, serde::Deserialize)]
+struct Position(u32, u32);
+
+/// Runs `FRAMES` frames that change every entity, returning the mean frame time
+fn measure(name: &str, setup: impl FnOnce(&mut App)) -> Duration {
+    let location = std::env::temp_dir().join(format!(
+        "bevy_easy_database_bench_{name}_{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&location);
+
+    let elapsed = {
+        let mut app = App::new();
+        app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()));
+        setup(&mut app);
+        app.add_plugins(DatabasePlugin)
+            .add_database_mapping::<Position>();
+        app.update();
+        app.world_mut()
+            .spawn_batch((0..ENTITIES).map(|index| Position(index, 0)));
+        app.update();
+
+        let start = Instant::now();
+        for _ in 0..FRAMES {
+            for mut position in app
+                .world_mut()
+                .query::<&mut Position>()
+                .iter_mut(app.world_mut())
+            {
+                position.1 += 1;
+            }
+            app.update();
+        }
+        flush_database_writes(app.world());
+        start.elapsed()
+    };
+
+    let _ = std::fs::remove_dir_all(&location);
+    elapsed / FRAMES
+}
+
+fn main() {
+    println!("{ENTITIES} changed entities per frame, mean over {FRAMES} frames");
+    let unbatched = measure("unbatched", |_| {});
+    println!("     unbatched: {unbatched:?} per frame");
+    let batch_writes = measure("batch_writes", |app| {
+        app.insert_resource(DatabaseBatchWrites);
+    });
+    println!("  batch_writes: {batch_writes:?} per frame");
+    let batch_window = measure("batch_window", |app| {
+        app.insert_resource(DatabaseBatchWindow(Duration::from_millis(100)));
+    });
+    println!("  batch_window: {batch_window:?} per frame");
+}
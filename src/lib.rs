@@ -1,7 +1,8 @@
 #![forbid(missing_docs)]
 #![forbid(unsafe_code)]
 #![forbid(future_incompatible)]
-#![forbid(dead_code)]
+// Deny rather than forbid, so helpers only some features use can allow it locally
+#![deny(dead_code)]
 //! Database Plugin for Bevy
 //!
 //! This module provides persistent storage capabilities for Bevy ECS components using the fjall database.
@@ -9,11 +10,21 @@
 
 use bevy_app::prelude::*;
 use bevy_ecs::prelude::*;
-use fjall::{Config, Keyspace, PartitionCreateOptions};
+use bevy_ecs::query::{QueryFilter, Spawned};
+use bevy_ecs::relationship::Relationship;
+use bevy_ecs::schedule::{InternedScheduleLabel, ScheduleLabel};
+use bevy_ecs::system::SystemParam;
+use bevy_reflect::{TypeInfo, Typed, VariantInfo};
+use fjall::{Config, Keyspace, PartitionCreateOptions, PartitionHandle, PersistMode};
 use serde::{Deserialize, Serialize};
 use std::any::{Any, TypeId};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 // ===== Core Plugin Structure =====
 
@@ -21,66 +32,3647 @@ use std::hash::{DefaultHasher, Hash, Hasher};
 #[derive(Resource)]
 pub struct DatabaseLocation(pub String);
 
+/// Resource that makes the database create the partitions of every registered type
+/// as soon as it's opened, in [`PreStartup`], instead of on first access.
+///
+/// Useful for tooling that inspects the database structure before anything is written.
+#[derive(Resource)]
+pub struct DatabasePrecreatePartitions;
+
+/// Resource that makes the save systems collect every write and removal of a frame
+/// into a single batch, committed atomically in [`Last`].
+///
+/// An entity swapping one component for another (removing `A`, inserting `B`) then
+/// becomes durable as a unit, there is no point where neither is persisted. Batched
+/// writes become visible to readers only once the batch is committed at the end of
+/// the frame, [`save_type_now`] still writes immediately.
+#[derive(Resource)]
+pub struct DatabaseBatchWrites;
+
+/// Resource that makes the save systems count how many times each record is written,
+/// readable with [`DatabaseReader::write_count`].
+///
+/// Counting costs an extra read and write per save, so it's meant for finding out which
+/// entities change most while tuning save cadence.
+#[derive(Resource)]
+pub struct DatabaseWriteCounts;
+
+/// Resource that makes loading spawn entities in the order they were first persisted,
+/// so reloading reproduces the original spawn order (and the relative order of the
+/// entity ids allocated for them), for logic that depends on it.
+///
+/// Each entity gets a sequence number the first time one of its components is saved,
+/// which costs an extra read per save. Entities first saved in the same frame keep the
+/// order they were saved in.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// App::new()
+///     .insert_resource(DatabaseCreationOrder)
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+/// ```
+#[derive(Resource)]
+pub struct DatabaseCreationOrder;
+
+/// Resource that makes loading write back every record that decodes to something
+/// other than its stored bytes, e.g. because a `#[serde(default)]` field was filled in
+/// or a custom deserializer upgraded an old value.
+///
+/// Without it, migrated records keep their old bytes until the component next changes,
+/// so the old format lingers on disk and is migrated again on every start. The check
+/// costs a serialization per loaded record, plus a write per migrated one.
+///
+/// Types whose encoding isn't canonical, like those with `HashMap` fields, can serialize
+/// the same value to different bytes. A record that doesn't match its stored bytes is
+/// decoded and serialized a second time, and only written back if both attempts agree,
+/// so such records aren't rewritten on every start. The second attempt costs another
+/// deserialization and serialization per mismatched record.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// // Older versions allowed volumes above 1.0, such records are clamped and written back
+/// fn clamp<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+///     Ok(<f32 as serde::Deserialize>::deserialize(deserializer)?.clamp(0.0, 1.0))
+/// }
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Volume(#[serde(deserialize_with = "clamp")] pub f32);
+///
+/// App::new()
+///     .insert_resource(DatabaseResaveMigrated)
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Volume>();
+/// ```
+#[derive(Resource)]
+pub struct DatabaseResaveMigrated;
+
+/// Resource choosing what happens when the database can't be opened, e.g. because its
+/// files are corrupted. Without it, opening fails with a panic.
+///
+/// fjall always replays its journals while opening, recovering from crashes on its own.
+/// This only decides what happens when that isn't enough.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_recovery_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// std::fs::create_dir_all(&location).unwrap();
+/// std::fs::write(location.join("version"), "not a fjall database").unwrap();
+///
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .insert_resource(DatabaseRecovery::StartFresh)
+///     .add_plugins(DatabasePlugin);
+/// app.update();
+///
+/// let recovered = app.world().resource::<Messages<DatabaseRecovered>>();
+/// let moved_to = recovered.get_cursor().read(recovered).next().unwrap().moved_to.clone();
+/// assert!(moved_to.join("version").exists());
+/// # drop(app);
+/// # std::fs::remove_dir_all(&moved_to).unwrap();
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DatabaseRecovery {
+    /// Panic, leaving the files untouched
+    Fail,
+    /// Move the unreadable database aside, next to its location with a `.corrupted-<unix
+    /// time>` suffix, start with an empty one and send a [`DatabaseRecovered`] message
+    StartFresh,
+}
+
+/// Version of the on-disk layout described on [`partition_id`], stored in every
+/// database and bumped on incompatible changes to it
+pub const DATABASE_LAYOUT_VERSION: u16 = 1;
+
+/// Resource choosing what happens when the database was written with a different
+/// [`DATABASE_LAYOUT_VERSION`] than the one this build uses. Without it, opening fails
+/// with a panic, so records are never silently misread.
+///
+/// Databases created before the layout version was stored are treated as version 1.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// App::new()
+///     .insert_resource(VersionMismatchPolicy::Migrate(|keyspace, found| {
+///         // Rewrite the records written with layout `found` into the current one
+///         Ok(())
+///     }))
+///     .add_plugins(DatabasePlugin);
+/// ```
+#[derive(Resource, Clone, Copy, Debug)]
+pub enum VersionMismatchPolicy {
+    /// Panic, leaving the files untouched
+    Refuse,
+    /// Run the function with the keyspace and the version found, so it can rewrite the
+    /// records into the current layout. The database is marked as current once it
+    /// returns `Ok`, opening panics if it returns an error.
+    Migrate(fn(&Keyspace, u16) -> Result<(), String>),
+    /// Load the database as it is and send a [`DatabaseVersionMismatch`] message.
+    /// Records may fail to load or be misread. The stored version is left unchanged.
+    LoadBestEffort,
+}
+
+/// Resource limiting how much time the save systems spend serializing per frame.
+///
+/// The budget is split evenly between the mapped types. Once a type used up its share,
+/// its changed components are deferred to the next frames instead of being written,
+/// and are saved before newer changes. Every type serializes at least one component
+/// per frame, so a type with many changes can't starve the others.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_easy_database::*;
+/// use std::time::Duration;
+///
+/// App::new()
+///     .insert_resource(SerializationTimeBudget(Duration::from_millis(2)))
+///     .add_plugins(DatabasePlugin);
+/// ```
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct SerializationTimeBudget(pub Duration);
+
+/// Resource that batches writes like [`DatabaseBatchWrites`], but commits them at most
+/// once per window instead of every frame.
+///
+/// For very write-heavy apps this cuts commit overhead, and a key written many times
+/// during a window is committed only once. The tradeoff is durability: a crash loses
+/// everything written since the last commit, up to a whole window, and
+/// [`DatabaseReader`] doesn't see writes until they're committed. Use
+/// [`flush_database_writes`] to commit early, e.g. before quitting.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+/// use std::time::Duration;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Position(pub i32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_window_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .insert_resource(DatabaseBatchWindow(Duration::from_secs(60)))
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Position>();
+/// app.update();
+///
+/// let entity = app.world_mut().spawn(Position(1)).id();
+/// app.update();
+/// let reader = app.world().resource::<DatabaseReader>().clone();
+/// assert!(reader.get_persisted::<Position>(entity).is_none());
+///
+/// flush_database_writes(app.world());
+/// assert_eq!(reader.get_persisted::<Position>(entity).map(|p| p.0), Some(1));
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct DatabaseBatchWindow(pub Duration);
+
 /// Resource wrapper around fjall Keyspace
 #[derive(Resource, Clone, bevy_derive::Deref, bevy_derive::DerefMut)]
 pub struct KeyspaceWrapper(pub Keyspace);
 
-#[derive(Default, Resource, bevy_derive::Deref, bevy_derive::DerefMut)]
-struct DatabaseLoadMapper(pub HashMap<Entity, Entity>);
+impl KeyspaceWrapper {
+    /// Names of every partition in the keyspace, including ones no registered type uses
+    pub fn list_partitions(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .0
+            .list_partitions()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Iterates over the raw key and value bytes of a partition, in key order, as of the
+    /// moment this is called. Yields nothing if the partition doesn't exist.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Score(pub u32);
+    ///
+    /// let location = std::env::temp_dir().join("bevy_easy_database_raw_doc");
+    /// # let _ = std::fs::remove_dir_all(&location);
+    /// let mut app = App::new();
+    /// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Score>();
+    /// app.update();
+    /// app.world_mut().spawn(Score(7));
+    /// app.update();
+    ///
+    /// let keyspace = app.world().resource::<KeyspaceWrapper>();
+    /// for partition in keyspace.list_partitions() {
+    ///     for (key, value) in keyspace.read_partition_raw(&partition) {
+    ///         println!("{partition}: {key:?} = {value:?}");
+    ///     }
+    /// }
+    /// assert_eq!(keyspace.read_partition_raw(&partition_id::<Score>()).count(), 1);
+    /// # drop(app);
+    /// # std::fs::remove_dir_all(&location).unwrap();
+    /// ```
+    pub fn read_partition_raw(&self, name: &str) -> impl Iterator<Item = (Vec<u8>, Vec<u8>)> {
+        let snapshot = self.partition_exists(name).then(|| {
+            self.open_partition(name, PartitionCreateOptions::default())
+                .expect("Failed to open partition")
+                .snapshot()
+        });
+
+        snapshot.into_iter().flat_map(|snapshot| {
+            snapshot
+                .iter()
+                .filter_map(Result::ok)
+                .map(|(key, value)| (key.to_vec(), value.to_vec()))
+        })
+    }
+}
+
+/// Maps the entities stored in the database to the live entities they were loaded into.
+///
+/// Entries are dropped as soon as the live entity is despawned, so the mapper never
+/// holds more entries than there are loaded entities alive.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// fn log_loaded(mapper: Res<DatabaseLoadMapper>) {
+///     for (stored, live) in mapper.iter() {
+///         println!("{stored} was loaded into {live}");
+///     }
+/// }
+///
+/// App::new()
+///     .add_plugins(DatabasePlugin)
+///     .add_systems(Startup, log_loaded);
+/// ```
+#[derive(Default, Resource)]
+pub struct DatabaseLoadMapper {
+    by_database: HashMap<Entity, Entity>,
+    by_live: HashMap<Entity, Entity>,
+}
+
+impl DatabaseLoadMapper {
+    /// Returns the live entity a database entity was loaded into
+    pub fn get(&self, database_entity: Entity) -> Option<Entity> {
+        self.by_database.get(&database_entity).copied()
+    }
+
+    /// Iterates over `(database entity, live entity)` pairs
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+        self.by_database
+            .iter()
+            .map(|(&database_entity, &live)| (database_entity, live))
+    }
+
+    /// Number of loaded entities currently tracked
+    pub fn len(&self) -> usize {
+        self.by_database.len()
+    }
+
+    /// Whether no loaded entities are tracked
+    pub fn is_empty(&self) -> bool {
+        self.by_database.is_empty()
+    }
+
+    /// Returns the database entity a live entity was loaded from
+    fn database_entity(&self, live: Entity) -> Option<Entity> {
+        self.by_live.get(&live).copied()
+    }
+
+    fn insert(&mut self, database_entity: Entity, live: Entity) {
+        self.by_database.insert(database_entity, live);
+        self.by_live.insert(live, database_entity);
+    }
+
+    fn clear(&mut self) {
+        self.by_database.clear();
+        self.by_live.clear();
+    }
+
+    fn remove_live(&mut self, live: Entity) {
+        if let Some(database_entity) = self.by_live.remove(&live) {
+            self.by_database.remove(&database_entity);
+        }
+    }
+}
+
+/// Component types registered for persistence, keyed by their TypeId
+#[derive(Default, Resource, Clone, bevy_derive::Deref, bevy_derive::DerefMut)]
+struct DatabaseRegistrations(HashMap<TypeId, RegisteredType>);
+
+impl DatabaseRegistrations {
+    /// Returns the registration of `T`, panicking if it was never registered
+    fn registered<T: Component>(&self) -> &RegisteredType {
+        self.get(&TypeId::of::<T>())
+            .expect("Component type is not registered for persistence")
+    }
+
+    /// Returns every registration, each one after the types it must be loaded after
+    fn in_load_order(&self) -> Vec<&RegisteredType> {
+        fn visit<'a>(
+            registrations: &'a DatabaseRegistrations,
+            type_id: TypeId,
+            visited: &mut HashSet<TypeId>,
+            order: &mut Vec<&'a RegisteredType>,
+        ) {
+            if !visited.insert(type_id) {
+                return;
+            }
+            let Some(registered) = registrations.get(&type_id) else {
+                return;
+            };
+            for &before in &registered.load_after {
+                visit(registrations, before, visited, order);
+            }
+            order.push(registered);
+        }
+
+        let mut visited = HashSet::with_capacity(self.len());
+        let mut order = Vec::with_capacity(self.len());
+        for &type_id in self.keys() {
+            visit(self, type_id, &mut visited, &mut order);
+        }
+        order
+    }
+}
+
+/// Serializes the registered component of an entity, `None` if it doesn't have one
+type SerializeHook = fn(&World, Entity) -> Result<Option<Vec<u8>>, DatabaseError>;
+
+/// Information about a single type registered for persistence
+#[derive(Clone)]
+struct RegisteredType {
+    type_name: &'static str,
+    partition_id: String,
+    /// User supplied namespace prepended to every record key
+    key_prefix: Vec<u8>,
+    /// Set when the type lives in a shared partition, tells its records apart from other types
+    type_tag: Option<u64>,
+    /// Serializes the entity's component, if it has one
+    serialize: SerializeHook,
+    /// Deserializes a component and inserts it on the entity
+    insert: fn(&mut EntityWorldMut, &[u8]) -> Result<(), FormatError>,
+    /// Loads every record of the type into a world
+    load: fn(&mut World),
+    /// Runs the type's save systems once, see [`save_type`]
+    save: fn(&mut World),
+    /// Types whose records must be loaded first, see
+    /// [`AddDatabaseMapping::set_database_load_after`]
+    load_after: Vec<TypeId>,
+    /// Set for relationship types, whose targets must be remapped between worlds
+    relationship: Option<RelationshipHooks>,
+    /// Set for lazy types, whose placeholders must be carried between worlds
+    lazy: Option<LazyHooks>,
+    /// How long records live after their last write, see
+    /// [`AddDatabaseMapping::add_database_mapping_ttl`]
+    ttl: Option<Duration>,
+}
+
+/// Reads and writes the target of a relationship type without knowing the type
+#[derive(Clone, Copy)]
+struct RelationshipHooks {
+    /// Returns the target of the entity's relationship, if it has one
+    target: fn(&World, Entity) -> Option<Entity>,
+    /// Relates the entity to a target
+    insert: fn(&mut EntityWorldMut, Entity),
+}
+
+/// Reads and writes the [`DatabaseLazy`] placeholder of a lazy type without knowing
+/// the type
+#[derive(Clone, Copy)]
+struct LazyHooks {
+    /// Returns whether the entity has the placeholder
+    contains: fn(&World, Entity) -> bool,
+    /// Marks the entity with the placeholder
+    insert: fn(&mut EntityWorldMut),
+}
+
+impl RegisteredType {
+    /// Opens the partition this type's records are stored in
+    fn open_partition(&self, keyspace: &Keyspace) -> PartitionHandle {
+        keyspace
+            .open_partition(&self.partition_id, PartitionCreateOptions::default())
+            .expect("Failed to open partition")
+    }
+
+    /// Prefix shared by every record key of this type
+    fn record_prefix(&self) -> Vec<u8> {
+        let mut prefix = self.key_prefix.clone();
+        if let Some(type_tag) = self.type_tag {
+            prefix.extend_from_slice(&type_tag.to_be_bytes());
+        }
+        prefix
+    }
+
+    /// Key of the record saved for an entity
+    fn key(&self, entity: Entity) -> Vec<u8> {
+        [self.record_prefix(), entity.index().to_be_bytes().to_vec()].concat()
+    }
+
+    /// Converts a record key back into the entity it was saved for
+    fn entity(&self, key: &[u8]) -> Option<Entity> {
+        entity_from_key(key.strip_prefix(self.record_prefix().as_slice())?)
+    }
+
+    /// Opens a partition holding extra data about this type's records (e.g. `"written"`
+    /// for write timestamps), keyed like the records themselves
+    fn open_side_partition(&self, keyspace: &Keyspace, name: &str) -> PartitionHandle {
+        keyspace
+            .open_partition(
+                &self.side_partition_id(name),
+                PartitionCreateOptions::default(),
+            )
+            .expect("Failed to open partition")
+    }
+
+    /// Name of a side partition, see [`RegisteredType::open_side_partition`]
+    fn side_partition_id(&self, name: &str) -> String {
+        format!("{}#{name}", self.partition_id)
+    }
+}
+
+/// Value used to seed a component on loaded entities that have no record for it
+#[derive(Resource)]
+struct DatabaseDefault<T>(Box<dyn Fn() -> T + Send + Sync>);
+
+/// Adds the bundle set with [`AddDatabaseMapping::set_load_spawn_bundle`] to a freshly
+/// spawned loaded entity
+#[derive(Resource)]
+struct DatabaseSpawnBundle(Box<dyn Fn(&mut EntityCommands) + Send + Sync>);
+
+/// Maximum number of records kept for `T`, see [`AddDatabaseMapping::add_database_mapping_capped`]
+#[derive(Resource)]
+struct DatabaseCap<T>(usize, PhantomData<fn() -> T>);
+
+/// Longest time between two sweeps for expired records
+const TTL_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Compares a changed component with the last persisted one, true if the change matters
+type SignificanceCheck<T> = Box<dyn Fn(&T, &T) -> bool + Send + Sync>;
+
+/// Checks a component before it's saved, returning why it's invalid
+type Validator<T> = Box<dyn Fn(&T) -> Result<(), String> + Send + Sync>;
+
+/// Invariants `T` must satisfy to be saved, see [`AddDatabaseMapping::set_database_validation`]
+#[derive(Resource)]
+struct DatabaseValidation<T> {
+    validate: Validator<T>,
+    on_failure: ValidationFailure,
+}
+
+/// Decides which changes of `T` are worth persisting, see
+/// [`AddDatabaseMapping::set_database_significance`]
+#[derive(Resource)]
+struct DatabaseSignificance<T> {
+    is_significant: SignificanceCheck<T>,
+    /// Copies a value to compare later changes against
+    remember: fn(&T) -> T,
+    last_persisted: Mutex<HashMap<Entity, T>>,
+}
+
+impl<T> DatabaseSignificance<T> {
+    /// Whether a change differs enough from the last persisted value to be persisted
+    fn should_persist(&self, entity: Entity, component: &T) -> bool {
+        let last_persisted = self
+            .last_persisted
+            .lock()
+            .expect("Significance lock poisoned");
+        last_persisted
+            .get(&entity)
+            .is_none_or(|old| (self.is_significant)(old, component))
+    }
+
+    /// Remembers a value once it was written, later changes are compared against it
+    fn persisted(&self, entity: Entity, component: &T) {
+        self.last_persisted
+            .lock()
+            .expect("Significance lock poisoned")
+            .insert(entity, (self.remember)(component));
+    }
+
+    /// Forgets the value persisted for an entity whose record was removed
+    fn forget(&self, entity: Entity) {
+        self.last_persisted
+            .lock()
+            .expect("Significance lock poisoned")
+            .remove(&entity);
+    }
+}
+
+/// Checks the save systems run on a component before writing it
+#[derive(SystemParam)]
+struct SaveChecks<'w, T: Component> {
+    validation: Option<Res<'w, DatabaseValidation<T>>>,
+    significance: Option<Res<'w, DatabaseSignificance<T>>>,
+}
+
+/// App name used to place the database in the platform's data directory
+#[derive(Resource)]
+struct DatabaseAppName(String);
+
+/// Main plugin struct for database functionality
+pub struct DatabasePlugin;
+
+impl DatabasePlugin {
+    /// Starts configuring the plugin, see [`DatabasePluginBuilder`]
+    pub fn builder() -> DatabasePluginBuilder {
+        DatabasePluginBuilder::default()
+    }
+}
+
+impl Plugin for DatabasePlugin {
+    fn build(&self, app: &mut App) {
+        DatabasePluginBuilder::default().build(app);
+    }
+}
+
+/// Configurable version of [`DatabasePlugin`], created with [`DatabasePlugin::builder`]
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// App::new().add_plugins(DatabasePlugin::builder().app_name("my_game"));
+/// ```
+#[derive(Default)]
+pub struct DatabasePluginBuilder {
+    app_name: Option<String>,
+    cleanup_schedule: Option<InternedScheduleLabel>,
+}
+
+impl DatabasePluginBuilder {
+    /// Stores the database in the platform's data directory, under a folder named
+    /// after the app, when no [`DatabaseLocation`] is set:
+    ///
+    /// * Linux: `$XDG_DATA_HOME/<app_name>` or `$HOME/.local/share/<app_name>`
+    /// * macOS: `$HOME/Library/Application Support/<app_name>`
+    /// * Windows: `%APPDATA%\<app_name>`
+    ///
+    /// These are the data directories the `directories` crate resolves too. They are
+    /// looked up from the environment directly rather than through that crate, so a
+    /// dependency isn't pulled in for three variables.
+    ///
+    /// Without an app name, the database defaults to `./database`, relative to the
+    /// current working directory, which is usually wrong for installed apps.
+    pub fn app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = Some(app_name.into());
+        self
+    }
+
+    /// Picks the schedule that removes [`DatabaseJustUpdated`] markers from freshly
+    /// loaded entities, [`PostUpdate`] by default.
+    ///
+    /// The save systems skip marked entities, so the cleanup must run after them once
+    /// per frame. Apps with a custom main schedule, where [`PostUpdate`] doesn't run
+    /// every frame, should pick a schedule that does.
+    pub fn cleanup_schedule(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.cleanup_schedule = Some(schedule.intern());
+        self
+    }
+}
+
+impl Plugin for DatabasePluginBuilder {
+    fn build(&self, app: &mut App) {
+        if let Some(app_name) = &self.app_name {
+            app.insert_resource(DatabaseAppName(app_name.clone()));
+        }
+
+        // Initialize database early
+        app.add_systems(PreStartup, setup_database);
+        app.add_systems(Startup, spawn_in_creation_order);
+        app.add_systems(Update, apply_ignore_policy);
+        app.add_systems(
+            self.cleanup_schedule.unwrap_or(PostUpdate.intern()),
+            cleanup_update_markers,
+        );
+        app.add_systems(
+            Last,
+            (
+                commit_write_batch,
+                update_database_metrics,
+                reset_serialization_time,
+            )
+                .chain(),
+        );
+        app.init_resource::<DatabaseLoadMapper>();
+        app.init_resource::<DatabaseRegistrations>();
+        app.init_resource::<DatabaseFormats>();
+        app.init_resource::<DatabaseMetrics>();
+        app.init_resource::<SerializationTimeSpent>();
+        app.add_message::<DatabaseChange>();
+        app.add_message::<DatabaseIoActivity>();
+        app.add_message::<DatabaseError>();
+        app.add_message::<DatabaseRecovered>();
+        app.add_message::<SchemaChanged>();
+        app.add_message::<DatabaseVersionMismatch>();
+    }
+}
+
+// ===== Component Markers =====
+
+/// Marker component indicating the entity was just updated from the database
+#[derive(Component)]
+pub struct DatabaseJustUpdated;
+
+/// Marker component to exclude an entity from database operations.
+///
+/// Adding it to an entity that was already persisted stops further saves. Whether its
+/// existing records are kept or deleted is decided by [`DatabaseIgnorePolicy`].
+#[derive(Component)]
+pub struct DatabaseIgnore;
+
+/// Resource deciding what happens to the records of an already persisted entity when
+/// [`DatabaseIgnore`] is added to it. Defaults to [`DatabaseIgnorePolicy::KeepRecords`].
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_ignore_policy_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .insert_resource(DatabaseIgnorePolicy::DeleteRecords)
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+/// app.update();
+///
+/// let entity = app.world_mut().spawn(Score(1)).id();
+/// app.update();
+/// app.world_mut().entity_mut(entity).insert(DatabaseIgnore);
+/// app.update();
+///
+/// let reader = app.world().resource::<DatabaseReader>();
+/// assert!(reader.get_persisted::<Score>(entity).is_none());
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DatabaseIgnorePolicy {
+    /// Leave the records as they were, they're loaded again on the next start. Removing
+    /// [`DatabaseIgnore`] later resumes saving on the next change.
+    #[default]
+    KeepRecords,
+    /// Delete every record of the entity, as if it had been despawned
+    DeleteRecords,
+}
+
+/// Filter shared by the save systems, skipping entities that shouldn't be written
+type PersistFilter = (Without<DatabaseJustUpdated>, Without<DatabaseIgnore>);
+
+/// Placeholder for a `T` record that exists in the database but hasn't been loaded,
+/// see [`AddDatabaseMapping::add_database_mapping_lazy`]
+#[derive(Component)]
+pub struct DatabaseLazy<T: 'static>(PhantomData<fn() -> T>);
+
+/// Filter for entities that have a `T` record, loaded or not
+type HasRecord<T> = Or<(With<T>, With<DatabaseLazy<T>>)>;
+
+// ===== Serialization Formats =====
+
+/// Error returned by a [`DatabaseFormat`]
+pub type FormatError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Serialization format used to encode components in the database.
+///
+/// Components are stored with [`Bincode`] unless another format is picked with
+/// [`AddDatabaseMapping::set_database_format`], such as the bundled [`SelfDescribing`].
+/// Any serde format can be plugged in:
+///
+/// ```ignore
+/// pub struct Json;
+///
+/// impl DatabaseFormat for Json {
+///     fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, FormatError> {
+///         Ok(serde_json::to_vec(value)?)
+///     }
+///
+///     fn deserialize<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T, FormatError> {
+///         Ok(serde_json::from_slice(bytes)?)
+///     }
+/// }
+/// ```
+///
+/// Implementations must round-trip every value they accept. If a value can't be
+/// represented losslessly (for example 128-bit integers in a format limited to 64-bit
+/// numbers), return an error instead of truncating it: the save systems skip the write
+/// and send a [`DatabaseError::Serialization`] rather than silently persisting a
+/// different value. The record keeps its previous value.
+///
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// /// Bincode, limited to 16 bytes per record
+/// pub struct Small;
+///
+/// impl DatabaseFormat for Small {
+///     fn serialize<T: serde::Serialize>(value: &T) -> Result<Vec<u8>, FormatError> {
+///         let bytes = Bincode::serialize(value)?;
+///         match bytes.len() <= 16 {
+///             true => Ok(bytes),
+///             false => Err("record doesn't fit in 16 bytes".into()),
+///         }
+///     }
+///
+///     fn deserialize<T: for<'de> serde::Deserialize<'de>>(
+///         bytes: &[u8],
+///     ) -> Result<T, FormatError> {
+///         Bincode::deserialize(bytes)
+///     }
+/// }
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Name(pub String);
+///
+/// App::new()
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Name>()
+///     .set_database_format::<Name, Small>();
+/// ```
+pub trait DatabaseFormat: 'static {
+    /// Encodes a value into bytes
+    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, FormatError>;
+
+    /// Decodes a value from bytes
+    fn deserialize<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T, FormatError>;
+}
+
+/// Compact binary format, used by default.
+///
+/// Bincode is not self-describing: records don't contain field names, and a struct is
+/// read back field by field in declaration order. This means `#[serde(default)]` does
+/// **not** help with records written before a field was added, loading them fails
+/// because the bytes for the new field are missing. Types that are expected to gain
+/// fields over time should use a self-describing format (such as [`SelfDescribing`],
+/// JSON or CBOR), where missing fields fall back to their serde defaults.
+///
+/// Every primitive serde supports, including `u128`/`i128`, is stored losslessly.
+pub struct Bincode;
+
+impl DatabaseFormat for Bincode {
+    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, FormatError> {
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn deserialize<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T, FormatError> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+mod self_describing;
+pub use self_describing::SelfDescribing;
+
+/// Format functions for a single component type
+struct Codec<T> {
+    serialize: fn(&T) -> Result<Vec<u8>, FormatError>,
+    deserialize: fn(&[u8]) -> Result<T, FormatError>,
+}
+
+impl<T> Clone for Codec<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Codec<T> {}
+
+impl<T: Serialize + for<'de> Deserialize<'de>> Codec<T> {
+    fn of<F: DatabaseFormat>() -> Self {
+        Codec {
+            serialize: F::serialize::<T>,
+            deserialize: F::deserialize::<T>,
+        }
+    }
+}
+
+/// Formats picked for component types, types without an entry use [`Bincode`]
+#[derive(Default, Resource, Clone)]
+struct DatabaseFormats(HashMap<TypeId, Arc<dyn Any + Send + Sync>>);
+
+impl DatabaseFormats {
+    fn codec<T: Serialize + for<'de> Deserialize<'de> + Component>(&self) -> Codec<T> {
+        self.0
+            .get(&TypeId::of::<T>())
+            .and_then(|codec| codec.downcast_ref::<Codec<T>>())
+            .copied()
+            .unwrap_or_else(Codec::of::<Bincode>)
+    }
+}
+
+/// Looks up the codec of `T` in a world
+fn world_codec<T: Serialize + for<'de> Deserialize<'de> + Component>(world: &World) -> Codec<T> {
+    world
+        .get_resource::<DatabaseFormats>()
+        .map(DatabaseFormats::codec)
+        .unwrap_or_else(Codec::of::<Bincode>)
+}
+
+// ===== Database Messages =====
+
+/// Message sent for every record written by the save systems.
+///
+/// `bytes` is the exact buffer handed to fjall, so it can be forwarded elsewhere
+/// (e.g. to networked clients) without serializing the component a second time.
+/// Cloning it is cheap, the underlying allocation is shared.
+#[derive(Message, Clone)]
+pub struct DatabaseChange {
+    /// The entity whose component was written
+    pub entity: Entity,
+    /// Type name of the written component
+    pub type_name: &'static str,
+    /// Serialized component, as stored in the database
+    pub bytes: fjall::Slice,
+}
+
+/// Message sent when the database couldn't be opened and was replaced by an empty one,
+/// see [`DatabaseRecovery::StartFresh`]
+#[derive(Message, Clone, Debug)]
+pub struct DatabaseRecovered {
+    /// Why the database couldn't be opened
+    pub error: String,
+    /// Where the unreadable database was moved to
+    pub moved_to: PathBuf,
+}
+
+/// Message sent at startup when the structure of a type checked with
+/// [`AddDatabaseMapping::add_database_schema_check`] changed since the last start.
+///
+/// It is sent right before the records of the type are loaded. Records can be migrated
+/// in time with [`AddDatabaseMapping::add_database_schema_migration`], otherwise records
+/// that fail to decode are skipped and reported with a
+/// [`DatabaseError::Deserialization`].
+#[derive(Message, Clone, Debug)]
+pub struct SchemaChanged {
+    /// Type name of the changed type
+    pub type_name: &'static str,
+}
+
+/// Message sent when the database was loaded despite being written with a different
+/// layout version, see [`VersionMismatchPolicy::LoadBestEffort`]
+#[derive(Message, Clone, Debug)]
+pub struct DatabaseVersionMismatch {
+    /// Layout version stored in the database
+    pub found: u16,
+    /// Layout version this build uses, [`DATABASE_LAYOUT_VERSION`]
+    pub expected: u16,
+}
+
+/// Message sent when the database refused to persist or load something
+#[derive(Message, Clone, Debug)]
+pub enum DatabaseError {
+    /// A component failed the validation registered with
+    /// [`AddDatabaseMapping::set_database_validation`] and wasn't written
+    Validation {
+        /// The entity whose component failed validation
+        entity: Entity,
+        /// Type name of the component
+        type_name: &'static str,
+        /// Why the validation failed
+        reason: String,
+    },
+    /// The [`DatabaseFormat`] of a component type couldn't encode a value, so it
+    /// wasn't written
+    Serialization {
+        /// The entity whose component failed to serialize
+        entity: Entity,
+        /// Type name of the component
+        type_name: &'static str,
+        /// The error returned by the format
+        reason: String,
+    },
+    /// A record couldn't be decoded, so it was skipped while loading
+    Deserialization {
+        /// Type name of the component
+        type_name: &'static str,
+        /// Key the record is stored under
+        key: Vec<u8>,
+        /// Why the record couldn't be decoded
+        reason: String,
+    },
+}
+
+/// What the save systems do with a component that fails validation, see
+/// [`AddDatabaseMapping::set_database_validation`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationFailure {
+    /// Skip the write and send a [`DatabaseError::Validation`]
+    Skip,
+    /// Panic, for catching bugs during development
+    Panic,
+}
+
+/// Message sent when fjall completed memtable flushes or compactions since the
+/// previous frame. Useful to correlate frame hitches with background IO.
+#[derive(Message, Clone, Debug)]
+pub struct DatabaseIoActivity {
+    /// Memtable flushes completed since the previous frame
+    pub flushes: usize,
+    /// Compactions completed since the previous frame
+    pub compactions: usize,
+    /// Time spent compacting since the previous frame
+    pub time_compacting: Duration,
+}
+
+// ===== Database Setup and Management =====
+
+/// Resources configuring how the database is opened
+#[derive(SystemParam)]
+struct DatabaseSetup<'w> {
+    database_location: Option<Res<'w, DatabaseLocation>>,
+    app_name: Option<Res<'w, DatabaseAppName>>,
+    precreate_partitions: Option<Res<'w, DatabasePrecreatePartitions>>,
+    batch_writes: Option<Res<'w, DatabaseBatchWrites>>,
+    batch_window: Option<Res<'w, DatabaseBatchWindow>>,
+    recovery: Option<Res<'w, DatabaseRecovery>>,
+    version_mismatch: Option<Res<'w, VersionMismatchPolicy>>,
+}
+
+/// Initializes the database connection and creates the KeyspaceWrapper and DatabaseReader resources
+fn setup_database(
+    mut commands: Commands,
+    setup: DatabaseSetup,
+    registrations: Res<DatabaseRegistrations>,
+    formats: Res<DatabaseFormats>,
+    mut recovered: MessageWriter<DatabaseRecovered>,
+    mut mismatches: MessageWriter<DatabaseVersionMismatch>,
+) {
+    let database_location = setup
+        .database_location
+        .map(|a| PathBuf::from(&a.0))
+        .or_else(|| Some(platform_data_dir()?.join(&setup.app_name?.0)))
+        .unwrap_or(PathBuf::from("./database"));
+
+    // Finish a replacement that was interrupted between its two renames
+    let replaced_location = replaced_path(&database_location);
+    if !database_location.exists() && replaced_location.exists() {
+        std::fs::rename(&replaced_location, &database_location)
+            .expect("Failed to restore replaced database");
+    }
+
+    // fjall already recovers what it can from its journals while opening
+    let keyspace = match Config::new(&database_location).open() {
+        Ok(keyspace) => keyspace,
+        Err(error) if setup.recovery.as_deref() == Some(&DatabaseRecovery::StartFresh) => {
+            let seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let mut moved_to = database_location.as_os_str().to_owned();
+            moved_to.push(format!(".corrupted-{seconds}"));
+            let moved_to = PathBuf::from(moved_to);
+
+            std::fs::rename(&database_location, &moved_to)
+                .expect("Failed to move corrupted database aside");
+            recovered.write(DatabaseRecovered {
+                error: error.to_string(),
+                moved_to,
+            });
+
+            Config::new(&database_location)
+                .open()
+                .expect("Failed to open database keyspace")
+        }
+        Err(error) => panic!("Failed to open database keyspace: {error}"),
+    };
+
+    let metadata = open_metadata_partition(&keyspace);
+    let found = metadata
+        .get(LAYOUT_VERSION_KEY)
+        .expect("Failed to read from database")
+        .and_then(|bytes| <[u8; 2]>::try_from(bytes.as_ref()).ok())
+        .map_or(DATABASE_LAYOUT_VERSION, u16::from_be_bytes);
+    let mark_current = match setup.version_mismatch.as_deref() {
+        _ if found == DATABASE_LAYOUT_VERSION => true,
+        None | Some(VersionMismatchPolicy::Refuse) => {
+            panic!("Database has layout version {found}, this build uses {DATABASE_LAYOUT_VERSION}")
+        }
+        Some(VersionMismatchPolicy::Migrate(migrate)) => {
+            if let Err(error) = migrate(&keyspace, found) {
+                panic!("Failed to migrate database from layout version {found}: {error}");
+            }
+            true
+        }
+        Some(VersionMismatchPolicy::LoadBestEffort) => {
+            mismatches.write(DatabaseVersionMismatch {
+                found,
+                expected: DATABASE_LAYOUT_VERSION,
+            });
+            false
+        }
+    };
+    if mark_current {
+        metadata
+            .insert(LAYOUT_VERSION_KEY, DATABASE_LAYOUT_VERSION.to_be_bytes())
+            .expect("Failed to insert into database");
+    }
+
+    if setup.precreate_partitions.is_some() {
+        for registered in registrations.values() {
+            registered.open_partition(&keyspace);
+        }
+    }
+
+    if setup.batch_writes.is_some() || setup.batch_window.is_some() {
+        commands.insert_resource(DatabaseWriteBatch {
+            pending: Mutex::default(),
+            window: setup.batch_window.map_or(Duration::ZERO, |window| window.0),
+            opened: Mutex::new(Instant::now()),
+        });
+    }
+
+    commands.insert_resource(DatabaseReader {
+        keyspace: keyspace.clone(),
+        registrations: Arc::new(registrations.0.clone()),
+        formats: Arc::new(formats.clone()),
+    });
+    commands.insert_resource(KeyspaceWrapper(keyspace));
+    commands.insert_resource(DatabasePath(database_location));
+    // fjall's counters start over with every keyspace it opens
+    commands.insert_resource(DatabaseMetrics::default());
+}
+
+/// Partition holding data about the database itself, like its layout version
+const METADATA_PARTITION: &str = "metadata";
+
+/// Key of the layout version in the metadata partition
+const LAYOUT_VERSION_KEY: &str = "layout_version";
+
+/// Opens the partition holding data about the database itself
+fn open_metadata_partition(keyspace: &Keyspace) -> PartitionHandle {
+    keyspace
+        .open_partition(METADATA_PARTITION, PartitionCreateOptions::default())
+        .expect("Failed to open partition")
+}
+
+/// Directory of the open database
+#[derive(Resource)]
+struct DatabasePath(PathBuf);
+
+/// Where the active database is moved while [`atomic_replace_database`] swaps in a new one
+fn replaced_path(location: &Path) -> PathBuf {
+    let mut name = location.as_os_str().to_owned();
+    name.push(".replaced");
+    PathBuf::from(name)
+}
+
+/// Removes DatabaseJustUpdated markers after database operations
+fn cleanup_update_markers(mut commands: Commands, query: Query<Entity, With<DatabaseJustUpdated>>) {
+    for entity in query.iter() {
+        commands.entity(entity).remove::<DatabaseJustUpdated>();
+    }
+}
+
+/// Runs `backup` while the database is quiesced, so its directory can be copied safely.
+///
+/// Taking `&mut World` guarantees none of the save systems run until `backup` returns.
+/// Before calling it, everything written so far is synced to disk and any running
+/// background compactions are waited out, so the files on disk describe a consistent
+/// database. Call it from an exclusive system or between app updates.
+///
+/// fjall may still start a new compaction on its own while `backup` runs. The copy
+/// stays recoverable (fjall recovers from partially written segments the same way it
+/// recovers from a crash) but it may include leftover segment files.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_quiesce_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin);
+/// app.update();
+///
+/// with_quiesced(app.world_mut(), || {
+///     // Copy the database directory somewhere safe
+/// });
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+pub fn with_quiesced<R>(world: &mut World, backup: impl FnOnce() -> R) -> R {
+    if let Some(batch) = world.get_resource::<DatabaseWriteBatch>() {
+        batch.commit(world.resource::<KeyspaceWrapper>());
+    }
+    let keyspace = world.resource::<KeyspaceWrapper>();
+
+    keyspace
+        .persist(PersistMode::SyncAll)
+        .expect("Failed to persist database");
+    while keyspace.active_compactions() > 0 {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    backup()
+}
+
+/// Replaces the active database with the one at `temp_path`, for crash-safe saves that
+/// are written to a side database first.
+///
+/// The side database is synced to disk, the active one is synced and closed, and the
+/// directories are swapped with two renames: the active database is moved aside, the
+/// side database moved into its place, and the old one deleted. The database is then
+/// reopened from its usual location.
+///
+/// Every handle to either database, including clones of [`DatabaseReader`] and the
+/// `Keyspace` the side database was written with, must be dropped before calling this.
+/// The world is not reloaded, entities keep their components and keep saving into
+/// the new database.
+///
+/// Each rename is atomic when both paths are on the same filesystem, on Windows only
+/// for NTFS. A crash between the two renames leaves no database at the usual
+/// location, in that case the old database is restored when the plugin next opens it.
+/// Renames across filesystems fail and leave the active database in place.
+///
+/// Fails with [`std::io::ErrorKind::NotFound`] before touching anything if `temp_path`
+/// doesn't hold a fjall database, so a mistyped path can't replace the active database
+/// with an empty one. The database is reopened even if a rename fails. Once the swap is
+/// done, failing to sync the parent directory or to delete the old database only logs
+/// a warning, the old database is then deleted by the next replacement.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_replace_doc");
+/// let side = std::env::temp_dir().join("bevy_easy_database_replace_doc_side");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// # let _ = std::fs::remove_dir_all(&side);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin);
+/// app.update();
+///
+/// // Write the save to a side database, then swap it in
+/// {
+///     let side_keyspace = fjall::Config::new(&side).open().unwrap();
+///     let saves = side_keyspace.open_partition("saves", Default::default()).unwrap();
+///     saves.insert("slot", "complete").unwrap();
+/// }
+/// atomic_replace_database(app.world_mut(), &side).unwrap();
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+pub fn atomic_replace_database(
+    world: &mut World,
+    temp_path: impl AsRef<Path>,
+) -> std::io::Result<()> {
+    let temp_path = temp_path.as_ref();
+    let location = world.resource::<DatabasePath>().0.clone();
+    let replaced_location = replaced_path(&location);
+
+    // Opening a missing keyspace would create an empty one and swap it in
+    if !temp_path.join("version").is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No database at {}", temp_path.display()),
+        ));
+    }
+
+    Config::new(temp_path)
+        .open()
+        .map_err(std::io::Error::other)?
+        .persist(PersistMode::SyncAll)
+        .map_err(std::io::Error::other)?;
+
+    // Leftover of a replacement whose cleanup failed
+    if replaced_location.exists() {
+        std::fs::remove_dir_all(&replaced_location)?;
+    }
+
+    with_quiesced(world, || ());
+    world.remove_resource::<DatabaseWriteBatch>();
+    world.remove_resource::<DatabaseReader>();
+    world.remove_resource::<KeyspaceWrapper>();
+
+    let swapped = std::fs::rename(&location, &replaced_location).and_then(|()| {
+        std::fs::rename(temp_path, &location).or_else(|error| {
+            std::fs::rename(&replaced_location, &location)?;
+            Err(error)
+        })
+    });
+
+    // Whatever happened, the save systems need a keyspace to keep working
+    open_database(world);
+    swapped?;
+
+    // The swap is done, failing to clean up after it doesn't undo it
+    #[cfg(unix)]
+    if let Some(parent) = location.parent().filter(|parent| parent.exists()) {
+        if let Err(error) = std::fs::File::open(parent).and_then(|parent| parent.sync_all()) {
+            log::warn!(
+                "Failed to sync {} after replacing the database: {error}",
+                parent.display()
+            );
+        }
+    }
+    if let Err(error) = std::fs::remove_dir_all(&replaced_location) {
+        log::warn!(
+            "Failed to remove the replaced database at {}: {error}",
+            replaced_location.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Latest pending write of every `(partition, key)`, `None` for removals
+type PendingWrites = HashMap<(String, Vec<u8>), (PartitionHandle, Option<fjall::Slice>)>;
+
+/// Writes collected when [`DatabaseBatchWrites`] or [`DatabaseBatchWindow`] is set.
+///
+/// Only the latest write of each key is kept, so a key written every frame of a window
+/// is committed once.
+#[derive(Resource)]
+struct DatabaseWriteBatch {
+    pending: Mutex<PendingWrites>,
+    /// Minimum time between commits, zero to commit every frame
+    window: Duration,
+    /// When the oldest uncommitted write may have been made
+    opened: Mutex<Instant>,
+}
+
+impl DatabaseWriteBatch {
+    /// Queues a write, replacing any pending write of the same key
+    fn write(&self, partition: &PartitionHandle, key: Vec<u8>, value: Option<fjall::Slice>) {
+        self.pending
+            .lock()
+            .expect("Write batch lock poisoned")
+            .insert(
+                (partition.name.to_string(), key),
+                (partition.clone(), value),
+            );
+    }
+
+    /// Drops the pending write of a key, for writes made outside the batch
+    fn discard(&self, partition: &PartitionHandle, key: &[u8]) {
+        self.pending
+            .lock()
+            .expect("Write batch lock poisoned")
+            .remove(&(partition.name.to_string(), key.to_vec()));
+    }
+
+    /// Reads a key, seeing writes that aren't committed yet
+    fn read(&self, partition: &PartitionHandle, key: &[u8]) -> Option<fjall::Slice> {
+        let pending = self.pending.lock().expect("Write batch lock poisoned");
+        match pending.get(&(partition.name.to_string(), key.to_vec())) {
+            Some((_, value)) => value.clone(),
+            None => partition.get(key).expect("Failed to read from database"),
+        }
+    }
+
+    /// Commits every pending write atomically
+    fn commit(&self, keyspace: &Keyspace) {
+        let pending = std::mem::take(&mut *self.pending.lock().expect("Write batch lock poisoned"));
+        *self.opened.lock().expect("Write batch lock poisoned") = Instant::now();
+        if pending.is_empty() {
+            return;
+        }
+
+        let mut batch = keyspace.batch();
+        for ((_, key), (partition, value)) in pending {
+            match value {
+                Some(value) => batch.insert(&partition, key, value),
+                None => batch.remove(&partition, key),
+            }
+        }
+        batch.commit().expect("Failed to commit write batch");
+    }
+}
+
+/// Commits the write batch once its window has passed, if writes are batched
+fn commit_write_batch(keyspace: Res<KeyspaceWrapper>, batch: Option<Res<DatabaseWriteBatch>>) {
+    if let Some(batch) = batch {
+        if batch
+            .opened
+            .lock()
+            .expect("Write batch lock poisoned")
+            .elapsed()
+            >= batch.window
+        {
+            batch.commit(&keyspace);
+        }
+    }
+}
+
+/// Lists the records with a write or removal still waiting in the write batch, as
+/// `(partition name, entity)` pairs sorted by partition, for checking that
+/// [`DatabaseBatchWrites`] or [`DatabaseBatchWindow`] behave as expected.
+///
+/// Only records of registered types are listed, not bookkeeping like write counts.
+/// Returns nothing if writes aren't batched.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+/// use std::time::Duration;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Position(pub i32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_pending_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .insert_resource(DatabaseBatchWindow(Duration::from_secs(60)))
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Position>();
+/// app.update();
+///
+/// let entity = app.world_mut().spawn(Position(1)).id();
+/// app.update();
+/// assert_eq!(pending_writes(app.world()), [(partition_id::<Position>(), entity)]);
+///
+/// flush_database_writes(app.world());
+/// assert!(pending_writes(app.world()).is_empty());
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+pub fn pending_writes(world: &World) -> Vec<(String, Entity)> {
+    let Some(batch) = world.get_resource::<DatabaseWriteBatch>() else {
+        return Vec::new();
+    };
+    let registrations = world.resource::<DatabaseRegistrations>();
+
+    let mut pending: Vec<(String, Entity)> = batch
+        .pending
+        .lock()
+        .expect("Write batch lock poisoned")
+        .keys()
+        .filter_map(|(partition, key)| {
+            let entity = registrations
+                .values()
+                .filter(|registered| &registered.partition_id == partition)
+                .find_map(|registered| registered.entity(key))?;
+            Some((partition.clone(), entity))
+        })
+        .collect();
+    pending.sort_unstable();
+    pending
+}
+
+/// Commits every batched write right away, without waiting for the end of the frame or
+/// of the [`DatabaseBatchWindow`]. Does nothing if writes aren't batched.
+pub fn flush_database_writes(world: &World) {
+    if let Some(batch) = world.get_resource::<DatabaseWriteBatch>() {
+        batch.commit(world.resource::<KeyspaceWrapper>());
+    }
+}
+
+/// Inserts a record, going through the frame's write batch if there is one
+fn insert_record(
+    batch: Option<&DatabaseWriteBatch>,
+    partition: &PartitionHandle,
+    key: Vec<u8>,
+    value: fjall::Slice,
+) {
+    match batch {
+        Some(batch) => batch.write(partition, key, Some(value)),
+        None => partition
+            .insert(key, value)
+            .expect("Failed to insert into database"),
+    }
+}
+
+/// Removes a record, going through the frame's write batch if there is one
+fn remove_record(batch: Option<&DatabaseWriteBatch>, partition: &PartitionHandle, key: Vec<u8>) {
+    match batch {
+        Some(batch) => batch.write(partition, key, None),
+        None => partition
+            .remove(key)
+            .expect("Failed to remove from database"),
+    }
+}
+
+// ===== Component Persistence Trait =====
+
+/// Trait to add database mapping capabilities for components
+pub trait AddDatabaseMapping {
+    /// Adds database persistence for a component type. This will automatically handle
+    /// saving and loading the component to/from the database.
+    ///
+    /// # Type Parameters
+    /// * `T`: Component type that implements Serialize, Deserialize, and Component
+    ///
+    /// # Example
+    /// ```no_run
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Player(pub String);
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Score(pub u32);
+    ///
+    /// fn main() {
+    ///     App::new()
+    ///         //...
+    ///         .add_plugins(DatabasePlugin)
+    ///         // Register as many components as you need
+    ///         .add_database_mapping::<Player>()
+    ///         .add_database_mapping::<Score>()
+    ///         .run();
+    /// }
+    /// ```
+    fn add_database_mapping<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &mut self,
+    ) -> &mut Self;
+
+    /// Adds database persistence for a component type that is only written when the
+    /// component is first added to an entity. Later mutations are not persisted,
+    /// which keeps write volume down for write-once (immutable) data.
+    ///
+    /// Loading and removal behave exactly like [`AddDatabaseMapping::add_database_mapping`].
+    fn add_database_mapping_on_add<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &mut self,
+    ) -> &mut Self;
+
+    /// Adds database persistence for a component type, seeding it with `default` on
+    /// entities matching the filter `F` that don't have it. Seeded values are written
+    /// to the database immediately.
+    ///
+    /// Entities are seeded once, in the frame they are spawned, whether they were
+    /// loaded, moved in from a staged load or spawned at runtime. Removing the
+    /// component later doesn't seed it again.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Player;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Gold(pub u32);
+    ///
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Player>()
+    ///     // Every player starts with 100 gold
+    ///     .add_database_mapping_with_default::<_, With<Player>>(|| Gold(100));
+    /// ```
+    fn add_database_mapping_with_default<
+        T: Serialize + for<'de> Deserialize<'de> + Component,
+        F: QueryFilter + 'static,
+    >(
+        &mut self,
+        default: impl Fn() -> T + Send + Sync + 'static,
+    ) -> &mut Self;
+
+    /// Adds database persistence for a component type that keeps at most `max` records,
+    /// for ring-buffer style data like recent events.
+    ///
+    /// The time each record is first written is stored alongside it. Whenever new
+    /// records push the type over its cap, the component is removed from the entities
+    /// with the oldest records, which deletes those records. The entities themselves
+    /// are not despawned. Records written before the type was capped count as oldest.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct RecentEvent(pub u32);
+    ///
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     // Keep the 100 most recent events
+    ///     .add_database_mapping_capped::<RecentEvent>(100);
+    /// ```
+    fn add_database_mapping_capped<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &mut self,
+        max: usize,
+    ) -> &mut Self;
+
+    /// Adds database persistence for a component type whose records expire `ttl` after
+    /// they were last written, for temporary data like buffs or session tokens.
+    ///
+    /// The time of each write is stored alongside the record. Expired records are
+    /// skipped and deleted on load, and a sweep running at most once per second (or
+    /// once per `ttl`, if shorter) deletes them at runtime and removes the component
+    /// from their entities. The entities themselves are not despawned. Records written
+    /// before the type had a TTL expire `ttl` after they are first loaded.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Buff(pub u32);
+    ///
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     // Buffs wear off after ten minutes, even while the game isn't running
+    ///     .add_database_mapping_ttl::<Buff>(Duration::from_secs(600));
+    /// ```
+    fn add_database_mapping_ttl<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &mut self,
+        ttl: Duration,
+    ) -> &mut Self;
+
+    /// Adds database persistence for a component type that isn't loaded at startup.
+    ///
+    /// Entities with a record for `T` get a [`DatabaseLazy<T>`] placeholder instead of
+    /// the component. Queue [`load_lazy_component`] on an entity to read its record and
+    /// replace the placeholder with the component. Saving and removal work as usual once
+    /// the component is present, despawning an entity that still has the placeholder
+    /// deletes its record.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Terrain(pub Vec<u8>);
+    ///
+    /// #[derive(Component)]
+    /// pub struct NearPlayer;
+    ///
+    /// // Only read the terrain of chunks the player gets close to
+    /// fn load_nearby_terrain(
+    ///     mut commands: Commands,
+    ///     chunks: Query<Entity, (With<NearPlayer>, With<DatabaseLazy<Terrain>>)>,
+    /// ) {
+    ///     for chunk in &chunks {
+    ///         commands.entity(chunk).queue(load_lazy_component::<Terrain>);
+    ///     }
+    /// }
+    ///
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping_lazy::<Terrain>()
+    ///     .add_systems(Update, load_nearby_terrain);
+    /// ```
+    fn add_database_mapping_lazy<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &mut self,
+    ) -> &mut Self;
+
+    /// Picks the serialization format used to store a component type, see [`DatabaseFormat`].
+    /// Types default to [`Bincode`].
+    ///
+    /// Changing the format of a type that already has records makes them unreadable,
+    /// since existing records are not converted.
+    fn set_database_format<
+        T: Serialize + for<'de> Deserialize<'de> + Component,
+        F: DatabaseFormat,
+    >(
+        &mut self,
+    ) -> &mut Self;
+
+    /// Stores a registered component type in a partition shared with other types,
+    /// instead of a partition of its own. Must be called after the type is registered.
+    ///
+    /// Every fjall partition carries its own memtable, journal bookkeeping and segment
+    /// files, so apps with many small component types can group them to keep the
+    /// partition count down. Records in a shared partition are keyed by the type's tag
+    /// followed by the entity, so each type still loads by scanning only its own records.
+    ///
+    /// Tradeoffs: keys grow by 8 bytes, compaction of the shared partition rewrites the
+    /// data of every type in it, and moving a type into or out of a shared partition
+    /// leaves its existing records behind in the old location.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Health(pub u32);
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Mana(pub u32);
+    ///
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Health>()
+    ///     .add_database_mapping::<Mana>()
+    ///     .set_database_partition::<Health>("stats")
+    ///     .set_database_partition::<Mana>("stats");
+    /// ```
+    fn set_database_partition<T: Component>(&mut self, name: &str) -> &mut Self;
+
+    /// Adds database persistence for a component type, prepending `prefix` to the key
+    /// of every record it stores. Saving, loading and removal all use the prefixed keys.
+    ///
+    /// This lets the database follow a key convention expected by external, non-bevy
+    /// consumers. It only changes the keys, the partition stays the same, see
+    /// [`AddDatabaseMapping::set_database_partition`] for that.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Player(pub String);
+    ///
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping_with_key_prefix::<Player>("players/");
+    /// ```
+    fn add_database_mapping_with_key_prefix<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &mut self,
+        prefix: impl Into<Vec<u8>>,
+    ) -> &mut Self;
+
+    /// Makes the load of `T` run after the load of `Before`, for data that references
+    /// other persisted data (e.g. memberships referencing factions). The order applies
+    /// to the startup load, [`reload_world`] and [`DatabaseReader::load_staging_world`].
+    ///
+    /// `T` must already be registered.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Faction(pub String);
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Membership(pub u32);
+    ///
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Faction>()
+    ///     .add_database_mapping::<Membership>()
+    ///     // Observers of `Membership` can look up every faction
+    ///     .set_database_load_after::<Membership, Faction>();
+    /// ```
+    fn set_database_load_after<T: Component, Before: Component>(&mut self) -> &mut Self;
+
+    /// Persists the [`Name`] of every entity, so loaded entities keep their names for
+    /// debugging and tooling. Only the string is stored, the name's hash is recomputed
+    /// when it's loaded.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_name_persistence();
+    /// ```
+    fn add_name_persistence(&mut self) -> &mut Self;
+
+    /// Adds database persistence for a relationship type, like bevy's `ChildOf` or a
+    /// custom `#[relationship]` component.
+    ///
+    /// The related entity is stored by the same identity records are keyed by and
+    /// remapped to the live entity it was loaded into. A related entity that hasn't
+    /// been loaded yet, or has no records of its own, is spawned right away and filled
+    /// in by the loads of its own types, so load order doesn't matter. The relationship
+    /// target component (e.g. `Children`) is maintained by bevy and isn't stored.
+    ///
+    /// [`apply_staging_world`] remaps targets to the entities it moves. Targets only make
+    /// sense in the world they come from, so relationships are left out of
+    /// [`snapshot_entity`] and [`export_entity`]. Relationships are saved on every
+    /// change: validation, significance checks and the [`SerializationTimeBudget`] don't
+    /// apply to them, while write counts and creation order do.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Item(pub String);
+    ///
+    /// // Items come back inside the inventories they were in
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Item>()
+    ///     .add_database_relationship::<ChildOf>();
+    /// ```
+    fn add_database_relationship<R: Relationship>(&mut self) -> &mut Self;
+
+    /// Attaches a bundle to every entity the load spawns, for runtime-only components
+    /// loaded entities need to be usable right away, like visibility or physics state.
+    ///
+    /// Only entities spawned by the load get the bundle, loaded data put on entities
+    /// that already exist leaves them alone. Entities moved in by
+    /// [`apply_staging_world`] get it too. Setting it again replaces the bundle.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Position(pub i32);
+    ///
+    /// #[derive(Component, Default)]
+    /// pub struct Velocity(pub i32);
+    ///
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Position>()
+    ///     .set_load_spawn_bundle(Velocity::default);
+    /// ```
+    fn set_load_spawn_bundle<B: Bundle>(
+        &mut self,
+        bundle: impl Fn() -> B + Send + Sync + 'static,
+    ) -> &mut Self;
+
+    /// Checks every `T` against `validate` before the save systems write it, so invalid
+    /// data (negative health, impossible enum combinations) never reaches disk.
+    ///
+    /// A component that fails is not written. Depending on `on_failure`, a
+    /// [`DatabaseError::Validation`] is sent or the save system panics.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, serde::Serialize, serde::Deserialize)]
+    /// pub struct Health(pub i32);
+    ///
+    /// let location = std::env::temp_dir().join("bevy_easy_database_validation_doc");
+    /// # let _ = std::fs::remove_dir_all(&location);
+    /// let mut app = App::new();
+    /// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Health>()
+    ///     .set_database_validation::<Health>(
+    ///         |health| match health.0 >= 0 {
+    ///             true => Ok(()),
+    ///             false => Err(format!("negative health {}", health.0)),
+    ///         },
+    ///         ValidationFailure::Skip,
+    ///     );
+    /// app.update();
+    ///
+    /// let entity = app.world_mut().spawn(Health(-5)).id();
+    /// app.update();
+    ///
+    /// let reader = app.world().resource::<DatabaseReader>();
+    /// assert!(reader.get_persisted::<Health>(entity).is_none());
+    /// let errors = app.world().resource::<Messages<DatabaseError>>();
+    /// assert_eq!(errors.len(), 1);
+    /// # drop(app);
+    /// # std::fs::remove_dir_all(&location).unwrap();
+    /// ```
+    fn set_database_validation<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &mut self,
+        validate: impl Fn(&T) -> Result<(), String> + Send + Sync + 'static,
+        on_failure: ValidationFailure,
+    ) -> &mut Self;
+
+    /// Only persists changes of `T` that `is_significant(old, new)` accepts, comparing
+    /// against the value last persisted for the entity. Cuts writes for components that
+    /// change continuously but trivially, like sensor readings or positions.
+    ///
+    /// The last persisted value of every entity is kept in memory. The first change of
+    /// an entity after startup is always persisted, as nothing is known about it yet.
+    /// Insignificant changes are dropped, not delayed: the database keeps the last
+    /// significant value until another significant change comes in.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    ///
+    /// #[derive(Component, Clone, serde::Serialize, serde::Deserialize)]
+    /// pub struct Health(pub i32);
+    ///
+    /// let location = std::env::temp_dir().join("bevy_easy_database_significance_doc");
+    /// # let _ = std::fs::remove_dir_all(&location);
+    /// let mut app = App::new();
+    /// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Health>()
+    ///     .set_database_significance::<Health>(|old, new| (old.0 - new.0).abs() > 5);
+    /// app.update();
+    ///
+    /// let entity = app.world_mut().spawn(Health(100)).id();
+    /// app.update();
+    /// let persisted = |app: &App| {
+    ///     let reader = app.world().resource::<DatabaseReader>();
+    ///     reader.get_persisted::<Health>(entity).map(|health| health.0)
+    /// };
+    ///
+    /// app.world_mut().get_mut::<Health>(entity).unwrap().0 = 97;
+    /// app.update();
+    /// assert_eq!(persisted(&app), Some(100));
+    ///
+    /// app.world_mut().get_mut::<Health>(entity).unwrap().0 = 90;
+    /// app.update();
+    /// assert_eq!(persisted(&app), Some(90));
+    /// # drop(app);
+    /// # std::fs::remove_dir_all(&location).unwrap();
+    /// ```
+    fn set_database_significance<T: Serialize + for<'de> Deserialize<'de> + Component + Clone>(
+        &mut self,
+        is_significant: impl Fn(&T, &T) -> bool + Send + Sync + 'static,
+    ) -> &mut Self;
+
+    /// Detects changes to the structure of a registered type between starts, sending
+    /// [`SchemaChanged`] before the type is loaded.
+    ///
+    /// A hash of the type's reflected structure (field names and field types, or variants
+    /// for enums) is stored in the database's `metadata` partition and compared on every
+    /// start. Only the type itself is hashed, changes inside its field types go unnoticed.
+    /// The stored hash is updated right after the comparison, so the message is sent on
+    /// the first start after a change only.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    /// use bevy_reflect::Reflect;
+    ///
+    /// #[derive(Component, Reflect, serde::Serialize, serde::Deserialize)]
+    /// pub struct Health(pub u32);
+    ///
+    /// fn report_schema_changes(mut changes: MessageReader<SchemaChanged>) {
+    ///     for change in changes.read() {
+    ///         println!("{} changed since the last start", change.type_name);
+    ///     }
+    /// }
+    ///
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Health>()
+    ///     .add_database_schema_check::<Health>()
+    ///     .add_systems(Update, report_schema_changes);
+    /// ```
+    fn add_database_schema_check<T: Component + Typed>(&mut self) -> &mut Self;
+
+    /// Like [`AddDatabaseMapping::add_database_schema_check`], but stores the hash under
+    /// `type_name` instead of the type's own name, e.g. to keep comparing against the
+    /// hash of a type that was renamed.
+    fn add_database_schema_check_as<T: Component + Typed>(
+        &mut self,
+        type_name: &'static str,
+    ) -> &mut Self;
+
+    /// Like [`AddDatabaseMapping::add_database_schema_check`], but also migrates the
+    /// records of `T` when its structure changed, before they are loaded. Use it instead
+    /// of the plain check, not in addition to it.
+    ///
+    /// `migrate` is called with the stored bytes of every record and returns the bytes
+    /// to store instead, typically by decoding the old version of the type and encoding
+    /// the new one. Records it fails on are left as they were and are reported with a
+    /// [`DatabaseError::Deserialization`]. Changes are only noticed against a hash stored
+    /// by an earlier start, so the check must already be in place before the type
+    /// changes.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
+    /// use bevy_reflect::Reflect;
+    ///
+    /// /// How `Health` was stored before it gained a maximum
+    /// #[derive(serde::Deserialize)]
+    /// pub struct OldHealth(pub u32);
+    ///
+    /// #[derive(Component, Reflect, serde::Serialize, serde::Deserialize)]
+    /// pub struct Health {
+    ///     pub current: u32,
+    ///     pub max: u32,
+    /// }
+    ///
+    /// App::new()
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Health>()
+    ///     .add_database_schema_migration::<Health>(|bytes| {
+    ///         let OldHealth(current) = Bincode::deserialize(bytes)?;
+    ///         Bincode::serialize(&Health { current, max: 100 })
+    ///     });
+    /// ```
+    fn add_database_schema_migration<T: Component + Typed>(
+        &mut self,
+        migrate: impl Fn(&[u8]) -> Result<Vec<u8>, FormatError> + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl AddDatabaseMapping for App {
+    fn add_database_mapping<T: Serialize + for<'de> Deserialize<'de> + Component + Any>(
+        &mut self,
+    ) -> &mut Self {
+        register_type::<T>(self);
+
+        // Add system for loading components from database on startup
+        self.add_systems(Startup, load_components::<T>.in_set(load_set::<T>()));
+
+        // Add system for saving component changes during runtime
+        self.add_systems(Update, save_component_changes::<T, Changed<T>>);
+
+        // Add system for handling component removal
+        self.add_systems(Update, handle_component_removal::<T>);
+
+        // Forget loaded entities once they're despawned
+        self.add_observer(forget_despawned_entity::<T>);
+
+        self
+    }
+
+    fn add_database_mapping_on_add<T: Serialize + for<'de> Deserialize<'de> + Component + Any>(
+        &mut self,
+    ) -> &mut Self {
+        register_type::<T>(self);
+
+        self.add_systems(Startup, load_components::<T>.in_set(load_set::<T>()));
+
+        // Only persist the initial value, mutations are ignored
+        self.add_systems(Update, save_component_changes::<T, Added<T>>);
+        registration_mut::<T>(self).save = save_changes::<T, Added<T>>;
+
+        self.add_systems(Update, handle_component_removal::<T>);
+
+        self.add_observer(forget_despawned_entity::<T>);
+
+        self
+    }
+
+    fn add_database_mapping_with_default<
+        T: Serialize + for<'de> Deserialize<'de> + Component + Any,
+        F: QueryFilter + 'static,
+    >(
+        &mut self,
+        default: impl Fn() -> T + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.add_database_mapping::<T>();
+
+        // Loaded entities are seeded once every type has finished loading, runtime
+        // spawns before the save systems run
+        self.insert_resource(DatabaseDefault::<T>(Box::new(default)));
+        self.add_systems(PostStartup, seed_missing_components::<T, F>);
+        self.add_systems(
+            Update,
+            seed_missing_components::<T, F>.before(save_component_changes::<T, Changed<T>>),
+        );
+        registration_mut::<T>(self).save = |world| {
+            world
+                .run_system_cached(seed_missing_components::<T, F>)
+                .expect("Failed to run seeding system");
+            save_changes::<T, Changed<T>>(world);
+        };
+
+        self
+    }
+
+    fn add_database_mapping_capped<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &mut self,
+        max: usize,
+    ) -> &mut Self {
+        self.add_database_mapping::<T>();
+
+        self.insert_resource(DatabaseCap::<T>(max, PhantomData));
+        self.add_systems(
+            Update,
+            evict_oldest_records::<T>.after(save_component_changes::<T, Changed<T>>),
+        );
+        registration_mut::<T>(self).save = |world| {
+            save_changes::<T, Changed<T>>(world);
+            world
+                .run_system_cached(evict_oldest_records::<T>)
+                .expect("Failed to run eviction system");
+        };
+
+        self
+    }
+
+    fn add_database_mapping_ttl<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &mut self,
+        ttl: Duration,
+    ) -> &mut Self {
+        self.add_database_mapping::<T>();
+
+        // Kept with the registration so staged loads expire records too
+        registration_mut::<T>(self).ttl = Some(ttl);
+        self.add_systems(
+            Update,
+            expire_records::<T>.after(save_component_changes::<T, Changed<T>>),
+        );
+        registration_mut::<T>(self).save = |world| {
+            save_changes::<T, Changed<T>>(world);
+            world
+                .run_system_cached(expire_records::<T>)
+                .expect("Failed to run expiry system");
+        };
+
+        self
+    }
+
+    fn add_database_mapping_lazy<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &mut self,
+    ) -> &mut Self {
+        register_type::<T>(self);
+
+        // Only mark which entities have a record, the records are read on demand
+        self.add_systems(Startup, load_lazy_placeholders::<T>.in_set(load_set::<T>()));
+
+        self.add_systems(Update, save_component_changes::<T, Changed<T>>);
+
+        self.add_systems(
+            Update,
+            (
+                handle_component_removal::<T>,
+                handle_placeholder_removal::<T>,
+            ),
+        );
+
+        self.add_observer(forget_despawned_entity::<T>);
+        self.add_observer(forget_despawned_entity::<DatabaseLazy<T>>);
+
+        let mut registered = registration_mut::<T>(self);
+        registered.load = |world| {
+            world
+                .run_system_cached(load_lazy_placeholders::<T>)
+                .expect("Failed to run load system");
+        };
+        registered.save = |world| {
+            save_changes::<T, Changed<T>>(world);
+            world
+                .run_system_cached(handle_placeholder_removal::<T>)
+                .expect("Failed to run removal system");
+        };
+        registered.lazy = Some(LazyHooks {
+            contains: |world, entity| world.get::<DatabaseLazy<T>>(entity).is_some(),
+            insert: |entity| {
+                entity.insert(DatabaseLazy::<T>(PhantomData));
+            },
+        });
+
+        self
+    }
+
+    fn set_database_format<
+        T: Serialize + for<'de> Deserialize<'de> + Component,
+        F: DatabaseFormat,
+    >(
+        &mut self,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<DatabaseFormats>()
+            .0
+            .insert(TypeId::of::<T>(), Arc::new(Codec::<T>::of::<F>()));
+
+        self
+    }
+
+    fn set_database_partition<T: Component>(&mut self, name: &str) -> &mut Self {
+        let mut registrations = self.world_mut().resource_mut::<DatabaseRegistrations>();
+        let registered = registrations
+            .get_mut(&TypeId::of::<T>())
+            .expect("Component type must be registered before setting its partition");
+
+        registered.partition_id = name.to_string();
+        registered.type_tag = Some(get_type_tag::<T>());
+
+        self
+    }
+
+    fn add_database_mapping_with_key_prefix<
+        T: Serialize + for<'de> Deserialize<'de> + Component,
+    >(
+        &mut self,
+        prefix: impl Into<Vec<u8>>,
+    ) -> &mut Self {
+        self.add_database_mapping::<T>();
+
+        self.world_mut()
+            .resource_mut::<DatabaseRegistrations>()
+            .get_mut(&TypeId::of::<T>())
+            .expect("Component type was just registered")
+            .key_prefix = prefix.into();
+
+        self
+    }
+
+    fn set_database_load_after<T: Component, Before: Component>(&mut self) -> &mut Self {
+        // Kept with the registration so reloads and staged loads use the same order
+        registration_mut::<T>(self)
+            .load_after
+            .push(TypeId::of::<Before>());
+
+        self.configure_sets(Startup, load_set::<T>().after(load_set::<Before>()))
+    }
+
+    fn add_name_persistence(&mut self) -> &mut Self {
+        self.add_database_mapping::<Name>()
+    }
+
+    fn add_database_relationship<R: Relationship>(&mut self) -> &mut Self {
+        register_relationship::<R>(self);
+
+        self.add_systems(Startup, load_relationships::<R>.in_set(load_set::<R>()));
+
+        self.add_systems(
+            Update,
+            (
+                save_relationship_changes::<R>,
+                handle_component_removal::<R>,
+            ),
+        );
+
+        self.add_observer(forget_despawned_entity::<R>);
+
+        self
+    }
+
+    fn set_load_spawn_bundle<B: Bundle>(
+        &mut self,
+        bundle: impl Fn() -> B + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.insert_resource(DatabaseSpawnBundle(Box::new(move |entity| {
+            entity.insert(bundle());
+        })))
+    }
+
+    fn add_database_schema_check<T: Component + Typed>(&mut self) -> &mut Self {
+        self.add_database_schema_check_as::<T>(std::any::type_name::<T>())
+    }
+
+    fn add_database_schema_check_as<T: Component + Typed>(
+        &mut self,
+        type_name: &'static str,
+    ) -> &mut Self {
+        self.add_systems(
+            Startup,
+            schema_check::<T>(type_name, None).before(load_set::<T>()),
+        )
+    }
+
+    fn add_database_schema_migration<T: Component + Typed>(
+        &mut self,
+        migrate: impl Fn(&[u8]) -> Result<Vec<u8>, FormatError> + Send + Sync + 'static,
+    ) -> &mut Self {
+        let check = schema_check::<T>(std::any::type_name::<T>(), Some(Box::new(migrate)));
+        self.add_systems(Startup, check.before(load_set::<T>()))
+    }
+
+    fn set_database_validation<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &mut self,
+        validate: impl Fn(&T) -> Result<(), String> + Send + Sync + 'static,
+        on_failure: ValidationFailure,
+    ) -> &mut Self {
+        self.insert_resource(DatabaseValidation::<T> {
+            validate: Box::new(validate),
+            on_failure,
+        })
+    }
+
+    fn set_database_significance<T: Serialize + for<'de> Deserialize<'de> + Component + Clone>(
+        &mut self,
+        is_significant: impl Fn(&T, &T) -> bool + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.insert_resource(DatabaseSignificance::<T> {
+            is_significant: Box::new(is_significant),
+            remember: T::clone,
+            last_persisted: Mutex::default(),
+        })
+    }
+}
+
+/// Rewrites the stored bytes of a record, see
+/// [`AddDatabaseMapping::add_database_schema_migration`]
+type SchemaMigration = Box<dyn Fn(&[u8]) -> Result<Vec<u8>, FormatError> + Send + Sync>;
+
+/// Builds the startup system comparing the schema hash of `T` stored under `type_name`,
+/// migrating the records of `T` with `migrate` if it changed
+fn schema_check<T: Component + Typed>(
+    type_name: &'static str,
+    migrate: Option<SchemaMigration>,
+) -> impl FnMut(
+    Res<KeyspaceWrapper>,
+    Res<DatabaseRegistrations>,
+    MessageWriter<SchemaChanged>,
+    MessageWriter<DatabaseError>,
+) {
+    move |keyspace, registrations, mut changed, mut errors| {
+        let metadata = open_metadata_partition(&keyspace);
+        let key = format!("schema/{type_name}");
+        let hash = schema_hash(T::type_info()).to_be_bytes();
+
+        let stored = metadata.get(&key).expect("Failed to read from database");
+        if stored.as_deref() == Some(&hash[..]) {
+            return;
+        }
+        if stored.is_some() {
+            changed.write(SchemaChanged { type_name });
+
+            // Records are rewritten in place, the load right after reads the new bytes
+            if let Some(migrate) = &migrate {
+                let registered = registrations.registered::<T>();
+                let partition = registered.open_partition(&keyspace);
+                for record in partition.prefix(registered.record_prefix()) {
+                    let Ok((record_key, value)) = record else {
+                        continue;
+                    };
+                    match migrate(&value) {
+                        Ok(migrated) => partition
+                            .insert(&*record_key, migrated)
+                            .expect("Failed to insert into database"),
+                        Err(reason) => {
+                            errors.write(DatabaseError::Deserialization {
+                                type_name: registered.type_name,
+                                key: record_key.to_vec(),
+                                reason: reason.to_string(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        metadata
+            .insert(key, hash)
+            .expect("Failed to insert into database");
+    }
+}
+
+/// System set containing the startup load of a single component type
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+struct DatabaseLoadSet(TypeId);
+
+/// Returns the load set of `T`
+fn load_set<T: Component>() -> DatabaseLoadSet {
+    DatabaseLoadSet(TypeId::of::<T>())
+}
+
+// ===== Database Operations =====
+
+/// Everything the persistence systems need to read and write records
+#[derive(SystemParam)]
+struct Database<'w> {
+    keyspace: Res<'w, KeyspaceWrapper>,
+    registrations: Res<'w, DatabaseRegistrations>,
+    formats: Res<'w, DatabaseFormats>,
+    write_batch: Option<Res<'w, DatabaseWriteBatch>>,
+    write_counts: Option<Res<'w, DatabaseWriteCounts>>,
+    creation_order: Option<Res<'w, DatabaseCreationOrder>>,
+    time_budget: Option<Res<'w, SerializationTimeBudget>>,
+    time_spent: Res<'w, SerializationTimeSpent>,
+}
+
+/// Time the save system of each type spent serializing this frame, a type is missing
+/// until it serialized something
+#[derive(Resource, Default)]
+struct SerializationTimeSpent(Mutex<HashMap<TypeId, Duration>>);
+
+/// Starts a new frame of the [`SerializationTimeBudget`]
+fn reset_serialization_time(time_spent: Res<SerializationTimeSpent>) {
+    time_spent
+        .0
+        .lock()
+        .expect("Time budget lock poisoned")
+        .clear();
+}
+
+impl Database<'_> {
+    /// Returns the partition, registration and codec used to store `T`
+    fn storage<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &self,
+    ) -> (PartitionHandle, &RegisteredType, Codec<T>) {
+        let registered = self.registrations.registered::<T>();
+        (
+            registered.open_partition(&self.keyspace),
+            registered,
+            self.formats.codec::<T>(),
+        )
+    }
+
+    /// Whether `T` used up its share of this frame's [`SerializationTimeBudget`]
+    fn over_time_budget<T: Component>(&self) -> bool {
+        let Some(budget) = &self.time_budget else {
+            return false;
+        };
+        let share = budget.0 / self.registrations.len().max(1) as u32;
+        self.time_spent
+            .0
+            .lock()
+            .expect("Time budget lock poisoned")
+            .get(&TypeId::of::<T>())
+            .is_some_and(|&spent| spent >= share)
+    }
+
+    /// Counts time spent serializing `T` against this frame's [`SerializationTimeBudget`]
+    fn spend_time<T: Component>(&self, elapsed: Duration) {
+        if self.time_budget.is_some() {
+            let mut spent = self.time_spent.0.lock().expect("Time budget lock poisoned");
+            *spent.entry(TypeId::of::<T>()).or_default() += elapsed;
+        }
+    }
+
+    /// Opens the partition holding the write counts of a type, if writes are counted
+    fn write_counts(&self, registered: &RegisteredType) -> Option<PartitionHandle> {
+        self.write_counts
+            .is_some()
+            .then(|| registered.open_side_partition(&self.keyspace, "writes"))
+    }
+
+    /// Gives an entity the next creation sequence number the first time it's saved, if
+    /// [`DatabaseCreationOrder`] is set
+    fn record_creation(&self, entity: Entity) {
+        if self.creation_order.is_none() {
+            return;
+        }
+
+        let batch = self.write_batch.as_deref();
+        let partition = open_creation_partition(&self.keyspace);
+        let key = entity.index().to_be_bytes();
+        if read_record(batch, &partition, &key).is_some() {
+            return;
+        }
+
+        let sequence = decode_u64(read_record(batch, &partition, CREATION_SEQUENCE_KEY));
+        insert_record(
+            batch,
+            &partition,
+            CREATION_SEQUENCE_KEY.to_vec(),
+            (sequence + 1).to_be_bytes().into(),
+        );
+        insert_record(
+            batch,
+            &partition,
+            key.to_vec(),
+            sequence.to_be_bytes().into(),
+        );
+    }
+}
+
+/// Partition holding the creation sequence number of every persisted entity
+const CREATION_PARTITION: &str = "creation";
+
+/// Key of the next creation sequence number, never a valid entity key as those are
+/// always 4 bytes long
+const CREATION_SEQUENCE_KEY: &[u8] = b"next_sequence";
+
+/// Opens the partition holding creation sequence numbers
+fn open_creation_partition(keyspace: &Keyspace) -> PartitionHandle {
+    keyspace
+        .open_partition(CREATION_PARTITION, PartitionCreateOptions::default())
+        .expect("Failed to open partition")
+}
+
+/// Spawns the entity of every stored record in the order it was first persisted, ahead
+/// of the component loads, if [`DatabaseCreationOrder`] is set
+fn spawn_in_creation_order(
+    mut commands: Commands,
+    mut loader: DatabaseLoader,
+    keyspace: Res<KeyspaceWrapper>,
+    registrations: Res<DatabaseRegistrations>,
+    creation_order: Option<Res<DatabaseCreationOrder>>,
+) {
+    if creation_order.is_none() {
+        return;
+    }
+
+    let partition = open_creation_partition(&keyspace);
+    let record_partitions: Vec<_> = registrations
+        .values()
+        .map(|registered| (registered, registered.open_partition(&keyspace)))
+        .collect();
+
+    let mut order = Vec::new();
+    let mut stale = Vec::new();
+    for entry in partition.iter() {
+        let Ok((key, value)) = entry else { continue };
+        let Some(database_entity) = entity_from_key(&key) else {
+            continue;
+        };
+
+        // Entities whose records were all removed don't come back
+        let has_records = record_partitions.iter().any(|(registered, partition)| {
+            partition
+                .contains_key(registered.key(database_entity))
+                .expect("Failed to read from database")
+        });
+        if has_records {
+            order.push((decode_u64(Some(value)), database_entity));
+        } else {
+            stale.push(key);
+        }
+    }
+
+    for key in stale {
+        partition
+            .remove(key)
+            .expect("Failed to remove from database");
+    }
+
+    order.sort_unstable();
+    for (_, database_entity) in order {
+        loader.insert(&mut commands, database_entity, ());
+    }
+}
+
+/// Loads components from the database during startup
+fn load_components<T: Serialize + for<'de> Deserialize<'de> + Component>(
+    mut commands: Commands,
+    mut loader: DatabaseLoader,
+    database: Database,
+    resave_migrated: Option<Res<DatabaseResaveMigrated>>,
+    mut errors: MessageWriter<DatabaseError>,
+) {
+    let (partition, registered, codec) = database.storage::<T>();
+    let timestamps = registered
+        .ttl
+        .map(|_| registered.open_side_partition(&database.keyspace, "saved"));
+    let now = unix_nanos();
+
+    for record in partition.prefix(registered.record_prefix()) {
+        let Ok((key, value)) = record else { continue };
+
+        if let (Some(ttl), Some(timestamps)) = (registered.ttl, &timestamps) {
+            match read_u64(timestamps, &key) {
+                // Written before the type had a TTL, it starts counting now
+                0 => timestamps
+                    .insert(&*key, now.to_be_bytes())
+                    .expect("Failed to insert into database"),
+                written_at
+                    if written_at
+                        < now.saturating_sub(u64::try_from(ttl.as_nanos()).unwrap_or(u64::MAX)) =>
+                {
+                    partition
+                        .remove(&*key)
+                        .expect("Failed to remove from database");
+                    timestamps
+                        .remove(&*key)
+                        .expect("Failed to remove from database");
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        // Corrupt records are reported and skipped, the rest still load
+        let (database_entity, component) = match decode_record(registered, codec, &key, &value) {
+            Ok(decoded) => decoded,
+            Err(reason) => {
+                errors.write(DatabaseError::Deserialization {
+                    type_name: registered.type_name,
+                    key: key.to_vec(),
+                    reason: reason.to_string(),
+                });
+                continue;
+            }
+        };
+
+        if resave_migrated.is_some() {
+            // A record the format can't encode again is left as it was
+            let current = (codec.serialize)(&component)
+                .ok()
+                .filter(|current| current != value.as_ref());
+            // Non-canonical encodings, like HashMap fields, can encode the same value
+            // differently every time, only records that encode the same way twice
+            // count as migrated
+            let migrated = current.filter(|current| {
+                (codec.deserialize)(value.as_ref())
+                    .ok()
+                    .and_then(|again| (codec.serialize)(&again).ok())
+                    .is_some_and(|again| again == *current)
+            });
+            if let Some(current) = migrated {
+                insert_record(
+                    database.write_batch.as_deref(),
+                    &partition,
+                    key.to_vec(),
+                    current.into(),
+                );
+            }
+        }
+
+        loader.insert(&mut commands, database_entity, component);
+    }
+}
+
+/// Marks every entity with a `T` record with a [`DatabaseLazy<T>`] placeholder during startup
+fn load_lazy_placeholders<T: Component>(
+    mut commands: Commands,
+    mut loader: DatabaseLoader,
+    keyspace: Res<KeyspaceWrapper>,
+    registrations: Res<DatabaseRegistrations>,
+) {
+    let registered = registrations.registered::<T>();
+    let partition = registered.open_partition(&keyspace);
+
+    for record in partition.prefix(registered.record_prefix()) {
+        let Ok((key, _)) = record else { continue };
+
+        if let Some(database_entity) = registered.entity(key.as_ref()) {
+            loader.insert(
+                &mut commands,
+                database_entity,
+                DatabaseLazy::<T>(PhantomData),
+            );
+        }
+    }
+}
+
+/// Loads the relationships of type `R` during startup, remapping their targets
+fn load_relationships<R: Relationship>(
+    mut commands: Commands,
+    mut loader: DatabaseLoader,
+    keyspace: Res<KeyspaceWrapper>,
+    registrations: Res<DatabaseRegistrations>,
+) {
+    let registered = registrations.registered::<R>();
+    let partition = registered.open_partition(&keyspace);
+
+    for record in partition.prefix(registered.record_prefix()) {
+        let Ok((key, value)) = record else { continue };
+        let (Some(database_entity), Some(target)) =
+            (registered.entity(key.as_ref()), relationship_target(&value))
+        else {
+            continue;
+        };
+
+        // Targets are keyed like records, by index only
+        let target = Entity::from_raw_u32(target.index()).expect("Entity index is valid");
+        let target = match loader.mapper.get(target) {
+            Some(live) => live,
+            // Not loaded yet, the target's own loads fill in the entity spawned here
+            None => {
+                loader.insert(&mut commands, target, ());
+                loader.mapper.get(target).expect("Target was just loaded")
+            }
+        };
+
+        loader.insert(&mut commands, database_entity, R::from(target));
+    }
+}
+
+/// Decodes the target entity stored in a relationship record
+fn relationship_target(bytes: &[u8]) -> Option<Entity> {
+    Entity::try_from_bits(u64::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// Resources the load systems use to put loaded data on live entities
+#[derive(SystemParam)]
+struct DatabaseLoader<'w> {
+    mapper: ResMut<'w, DatabaseLoadMapper>,
+    spawn_bundle: Option<Res<'w, DatabaseSpawnBundle>>,
+}
+
+impl DatabaseLoader<'_> {
+    /// Inserts loaded data on the live entity of a database entity, spawning it on first use
+    fn insert(&mut self, commands: &mut Commands, database_entity: Entity, loaded: impl Bundle) {
+        match self.mapper.get(database_entity) {
+            None => {
+                let mut entity = commands.spawn((loaded, DatabaseJustUpdated));
+                if let Some(spawn_bundle) = &self.spawn_bundle {
+                    (spawn_bundle.0)(&mut entity);
+                }
+                self.mapper.insert(database_entity, entity.id());
+            }
+            Some(entity) => {
+                commands
+                    .entity(entity)
+                    .insert((loaded, DatabaseJustUpdated));
+            }
+        }
+    }
+}
+
+/// Reads the record behind an entity's [`DatabaseLazy<T>`] placeholder and replaces the
+/// placeholder with the component. Does nothing for entities without the placeholder.
+///
+/// Queue it as an entity command:
+/// `commands.entity(entity).queue(load_lazy_component::<T>)`.
+pub fn load_lazy_component<T: Serialize + for<'de> Deserialize<'de> + Component>(
+    mut entity: EntityWorldMut,
+) {
+    if !entity.contains::<DatabaseLazy<T>>() {
+        return;
+    }
+
+    let live = entity.id();
+    let world = entity.world();
+    let database_entity = world
+        .resource::<DatabaseLoadMapper>()
+        .database_entity(live)
+        .unwrap_or(live);
+    let component = world
+        .resource::<DatabaseReader>()
+        .get_persisted::<T>(database_entity);
+
+    entity.remove::<DatabaseLazy<T>>();
+    if let Some(component) = component {
+        entity.insert((component, DatabaseJustUpdated));
+    }
+}
+
+/// Saves components matching the change filter `F` (e.g. `Changed<T>` or `Added<T>`) to the database
+fn save_component_changes<T: Serialize + for<'de> Deserialize<'de> + Component, F: QueryFilter>(
+    database: Database,
+    query: Query<Entity, (F, PersistFilter, With<T>)>,
+    persisted: Query<&T, PersistFilter>,
+    mut deferred: Local<Vec<Entity>>,
+    checks: SaveChecks<T>,
+    mut changes: MessageWriter<DatabaseChange>,
+    mut errors: MessageWriter<DatabaseError>,
+) {
+    let (partition, registered, codec) = database.storage::<T>();
+    let write_counts = database.write_counts(registered);
+
+    // Changes deferred by the time budget go first, so they can't be starved
+    let mut pending = std::mem::take(&mut *deferred);
+    let already_pending: HashSet<Entity> = pending.iter().copied().collect();
+    pending.extend(
+        query
+            .iter()
+            .filter(|entity| !already_pending.contains(entity)),
+    );
+
+    for (index, &entity) in pending.iter().enumerate() {
+        if database.over_time_budget::<T>() {
+            deferred.extend_from_slice(&pending[index..]);
+            break;
+        }
+        let Ok(component) = persisted.get(entity) else {
+            continue;
+        };
+
+        if let Some(validation) = &checks.validation {
+            if let Err(reason) = (validation.validate)(component) {
+                let type_name = std::any::type_name::<T>();
+                if validation.on_failure == ValidationFailure::Panic {
+                    panic!("Invalid {type_name} on {entity}: {reason}");
+                }
+                errors.write(DatabaseError::Validation {
+                    entity,
+                    type_name,
+                    reason,
+                });
+                continue;
+            }
+        }
+        if let Some(significance) = &checks.significance {
+            if !significance.should_persist(entity, component) {
+                continue;
+            }
+        }
+
+        let start = Instant::now();
+        let serialized = persist_component(
+            database.write_batch.as_deref(),
+            &partition,
+            registered,
+            codec,
+            entity,
+            component,
+        );
+        database.spend_time::<T>(start.elapsed());
+        let serialized = match serialized {
+            Ok(serialized) => serialized,
+            Err(error) => {
+                errors.write(error);
+                continue;
+            }
+        };
+        if let Some(significance) = &checks.significance {
+            significance.persisted(entity, component);
+        }
+        database.record_creation(entity);
+        if let Some(write_counts) = &write_counts {
+            count_write(
+                database.write_batch.as_deref(),
+                write_counts,
+                registered.key(entity),
+            );
+        }
+
+        // Share the serialized buffer with any listeners instead of discarding it
+        changes.write(DatabaseChange {
+            entity,
+            type_name: std::any::type_name::<T>(),
+            bytes: serialized,
+        });
+    }
+}
+
+/// Saves the targets of changed `R` relationships to the database
+fn save_relationship_changes<R: Relationship>(
+    database: Database,
+    query: Query<(Entity, &R), (Changed<R>, PersistFilter)>,
+    mut changes: MessageWriter<DatabaseChange>,
+) {
+    let registered = database.registrations.registered::<R>();
+    let partition = registered.open_partition(&database.keyspace);
+    let write_counts = database.write_counts(registered);
+
+    for (entity, relationship) in query.iter() {
+        let bytes = fjall::Slice::from(relationship.get().to_bits().to_be_bytes());
+        insert_record(
+            database.write_batch.as_deref(),
+            &partition,
+            registered.key(entity),
+            bytes.clone(),
+        );
+        database.record_creation(entity);
+        if let Some(write_counts) = &write_counts {
+            count_write(
+                database.write_batch.as_deref(),
+                write_counts,
+                registered.key(entity),
+            );
+        }
+
+        changes.write(DatabaseChange {
+            entity,
+            type_name: std::any::type_name::<R>(),
+            bytes,
+        });
+    }
+}
+
+/// Inserts and immediately saves the registered default on freshly spawned entities
+/// matching `F` that are missing `T`
+fn seed_missing_components<
+    T: Serialize + for<'de> Deserialize<'de> + Component,
+    F: QueryFilter + 'static,
+>(
+    mut commands: Commands,
+    database: Database,
+    default: Res<DatabaseDefault<T>>,
+    query: Query<Entity, (F, Without<T>, Spawned)>,
+    mut changes: MessageWriter<DatabaseChange>,
+    mut errors: MessageWriter<DatabaseError>,
+) {
+    let (partition, registered, codec) = database.storage::<T>();
+    let write_counts = database.write_counts(registered);
+
+    for entity in &query {
+        let component = (default.0)();
+        let serialized = match persist_component(
+            database.write_batch.as_deref(),
+            &partition,
+            registered,
+            codec,
+            entity,
+            &component,
+        ) {
+            Ok(serialized) => serialized,
+            Err(error) => {
+                errors.write(error);
+                continue;
+            }
+        };
+        database.record_creation(entity);
+        if let Some(write_counts) = &write_counts {
+            count_write(
+                database.write_batch.as_deref(),
+                write_counts,
+                registered.key(entity),
+            );
+        }
+        changes.write(DatabaseChange {
+            entity,
+            type_name: std::any::type_name::<T>(),
+            bytes: serialized,
+        });
+
+        // Already written above, so keep the save systems from writing it again
+        commands
+            .entity(entity)
+            .insert((component, DatabaseJustUpdated));
+    }
+}
+
+/// Handles removal of components from the database
+fn handle_component_removal<T: Component>(
+    database: Database,
+    mut removed: RemovedComponents<T>,
+    present: Query<(), With<T>>,
+    significance: Option<Res<DatabaseSignificance<T>>>,
+) {
+    let registered = database.registrations.registered::<T>();
+    let partition = registered.open_partition(&database.keyspace);
+    let write_counts = database.write_counts(registered);
+    let batch = database.write_batch.as_deref();
+
+    for entity in removed.read() {
+        // Removed and inserted again within the frame, the save systems persist the new value
+        if present.contains(entity) {
+            continue;
+        }
+
+        remove_record(batch, &partition, registered.key(entity));
+        if let Some(write_counts) = &write_counts {
+            remove_record(batch, write_counts, registered.key(entity));
+        }
+        if let Some(significance) = &significance {
+            significance.forget(entity);
+        }
+    }
+}
+
+/// Timestamps new records of a capped type and evicts the oldest ones once it holds
+/// more records than allowed
+fn evict_oldest_records<T: Component>(
+    mut commands: Commands,
+    database: Database,
+    cap: Res<DatabaseCap<T>>,
+    added: Query<Entity, (Added<T>, PersistFilter)>,
+    persisted: Query<Entity, (With<T>, Without<DatabaseIgnore>)>,
+    mut removed: RemovedComponents<T>,
+) {
+    let registered = database.registrations.registered::<T>();
+    let partition = registered.open_partition(&database.keyspace);
+    let timestamps = registered.open_side_partition(&database.keyspace, "written");
+    let batch = database.write_batch.as_deref();
+
+    for entity in removed.read() {
+        if !persisted.contains(entity) {
+            remove_record(batch, &timestamps, registered.key(entity));
+        }
+    }
+
+    if added.is_empty() {
+        return;
+    }
+
+    let now = unix_nanos();
+    for entity in added.iter() {
+        insert_record(
+            batch,
+            &timestamps,
+            registered.key(entity),
+            now.to_be_bytes().into(),
+        );
+    }
+
+    let excess = persisted.iter().count().saturating_sub(cap.0);
+    if excess == 0 {
+        return;
+    }
+
+    let mut by_age: Vec<(u64, Entity)> = persisted
+        .iter()
+        .map(|entity| {
+            // Batched timestamps aren't readable yet, and records from before the cap have none
+            let written_at = if added.contains(entity) {
+                now
+            } else {
+                read_u64(&timestamps, registered.key(entity))
+            };
+            (written_at, entity)
+        })
+        .collect();
+    by_age.sort_unstable();
+
+    for (_, entity) in by_age.into_iter().take(excess) {
+        remove_record(batch, &partition, registered.key(entity));
+        remove_record(batch, &timestamps, registered.key(entity));
+        commands.entity(entity).remove::<T>();
+    }
+}
+
+/// Stamps the write time of saved `T` records and deletes the ones whose TTL ran out
+fn expire_records<T: Component>(
+    mut commands: Commands,
+    database: Database,
+    changed: Query<Entity, (Changed<T>, PersistFilter)>,
+    persisted: Query<Entity, (With<T>, Without<DatabaseIgnore>)>,
+    mut removed: RemovedComponents<T>,
+    mut last_sweep: Local<Option<Instant>>,
+) {
+    let registered = database.registrations.registered::<T>();
+    let ttl = registered.ttl.expect("Type has no TTL");
+    let partition = registered.open_partition(&database.keyspace);
+    let timestamps = registered.open_side_partition(&database.keyspace, "saved");
+    let batch = database.write_batch.as_deref();
+
+    for entity in removed.read() {
+        if !persisted.contains(entity) {
+            remove_record(batch, &timestamps, registered.key(entity));
+        }
+    }
+
+    let now = unix_nanos();
+    for entity in changed.iter() {
+        insert_record(
+            batch,
+            &timestamps,
+            registered.key(entity),
+            now.to_be_bytes().into(),
+        );
+    }
+
+    if last_sweep.is_some_and(|last_sweep| last_sweep.elapsed() < TTL_SWEEP_INTERVAL.min(ttl)) {
+        return;
+    }
+    *last_sweep = Some(Instant::now());
+
+    // TTLs too long to count in nanoseconds never run out
+    let cutoff = now.saturating_sub(u64::try_from(ttl.as_nanos()).unwrap_or(u64::MAX));
+    let mut expired = HashSet::new();
+    for entry in timestamps.prefix(registered.record_prefix()) {
+        let Ok((key, written_at)) = entry else {
+            continue;
+        };
+        // Writes of this frame may still be waiting in the batch
+        if decode_u64(read_record(batch, &timestamps, &key).or(Some(written_at))) < cutoff {
+            remove_record(batch, &partition, key.to_vec());
+            remove_record(batch, &timestamps, key.to_vec());
+            expired.insert(key.to_vec());
+        }
+    }
+
+    for entity in persisted.iter() {
+        if expired.contains(&registered.key(entity)) {
+            commands.entity(entity).remove::<T>();
+        }
+    }
+}
+
+/// Deletes the record of entities that lost their [`DatabaseLazy<T>`] placeholder without
+/// loading the component, e.g. because they were despawned
+fn handle_placeholder_removal<T: Component>(
+    keyspace: Res<KeyspaceWrapper>,
+    registrations: Res<DatabaseRegistrations>,
+    write_batch: Option<Res<DatabaseWriteBatch>>,
+    mut removed: RemovedComponents<DatabaseLazy<T>>,
+    present: Query<(), HasRecord<T>>,
+) {
+    let registered = registrations.registered::<T>();
+    let partition = registered.open_partition(&keyspace);
+
+    for entity in removed.read() {
+        if present.contains(entity) {
+            continue;
+        }
+
+        remove_record(write_batch.as_deref(), &partition, registered.key(entity));
+    }
+}
+
+/// Deletes the records of persisted entities that were just marked with [`DatabaseIgnore`],
+/// if the [`DatabaseIgnorePolicy`] asks for it
+fn apply_ignore_policy(
+    database: Database,
+    policy: Option<Res<DatabaseIgnorePolicy>>,
+    database_load_mapper: Res<DatabaseLoadMapper>,
+    ignored: Query<EntityRef, Added<DatabaseIgnore>>,
+) {
+    if policy.as_deref().copied().unwrap_or_default() != DatabaseIgnorePolicy::DeleteRecords {
+        return;
+    }
+    let batch = database.write_batch.as_deref();
+
+    for entity in ignored.iter() {
+        // Loaded entities may have records of lazy types whose components aren't present
+        let persisted = database_load_mapper.database_entity(entity.id()).is_some()
+            || database
+                .registrations
+                .keys()
+                .any(|&type_id| entity.contains_type_id(type_id));
+        if !persisted {
+            continue;
+        }
+
+        // Records of loaded entities are keyed by the entity they were loaded from,
+        // while saves made since are keyed by the live entity
+        let live = entity.id();
+        let mut keyed_by = Vec::from_iter(database_load_mapper.database_entity(live));
+        let live_key_owner = Entity::from_raw_u32(live.index())
+            .and_then(|database_entity| database_load_mapper.get(database_entity));
+        if live_key_owner.is_none_or(|owner| owner == live) {
+            keyed_by.push(live);
+        }
+
+        for registered in database.registrations.values() {
+            let partition = registered.open_partition(&database.keyspace);
+            for &keyed_by in &keyed_by {
+                remove_record(batch, &partition, registered.key(keyed_by));
+            }
+        }
+    }
+}
+
+/// Drops despawned entities from the load mapper so it doesn't grow with entity churn
+fn forget_despawned_entity<T: Component>(
+    despawn: On<bevy_ecs::lifecycle::Despawn, T>,
+    mut database_load_mapper: ResMut<DatabaseLoadMapper>,
+) {
+    database_load_mapper.remove_live(despawn.entity);
+}
+
+// ===== Database Metrics =====
+
+/// Counters describing fjall's background IO, refreshed every frame in [`Last`].
+///
+/// fjall doesn't offer callbacks for its flush and compaction workers, so these are
+/// polled from its statistics. [`DatabaseIoActivity`] is sent whenever they advance.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct DatabaseMetrics {
+    /// Total memtable flushes completed
+    pub flushes_completed: usize,
+    /// Total compactions completed
+    pub compactions_completed: usize,
+    /// Compactions currently running
+    pub active_compactions: usize,
+    /// Total time spent compacting
+    pub time_compacting: Duration,
+    /// Size of the in-memory write buffer, in bytes
+    pub write_buffer_size: u64,
+    /// Disk space used by the database, in bytes
+    pub disk_space: u64,
+}
+
+/// Polls fjall's statistics and reports any flush or compaction activity
+fn update_database_metrics(
+    keyspace: Res<KeyspaceWrapper>,
+    mut metrics: ResMut<DatabaseMetrics>,
+    mut activity: MessageWriter<DatabaseIoActivity>,
+) {
+    let current = DatabaseMetrics {
+        flushes_completed: keyspace.flushes_completed(),
+        compactions_completed: keyspace.compactions_completed(),
+        active_compactions: keyspace.active_compactions(),
+        time_compacting: keyspace.time_compacting(),
+        write_buffer_size: keyspace.write_buffer_size(),
+        disk_space: keyspace.disk_space(),
+    };
+
+    let flushes = current
+        .flushes_completed
+        .saturating_sub(metrics.flushes_completed);
+    let compactions = current
+        .compactions_completed
+        .saturating_sub(metrics.compactions_completed);
+    if flushes > 0 || compactions > 0 {
+        activity.write(DatabaseIoActivity {
+            flushes,
+            compactions,
+            time_compacting: current
+                .time_compacting
+                .saturating_sub(metrics.time_compacting),
+        });
+    }
+
+    *metrics = current;
+}
+
+/// Disk usage around an explicit [`compact_database`] call
+#[derive(Clone, Debug)]
+pub struct CompactionReport {
+    /// Disk space used before compacting, in bytes
+    pub disk_space_before: u64,
+    /// Disk space used after compacting, in bytes
+    pub disk_space_after: u64,
+    /// How long the compaction blocked for
+    pub duration: Duration,
+}
+
+/// Runs a major compaction of every registered partition, blocking until it's done,
+/// and reports the database size before and after.
+pub fn compact_database(world: &World) -> CompactionReport {
+    let keyspace = world.resource::<KeyspaceWrapper>();
+    let registrations = world.resource::<DatabaseRegistrations>();
+
+    let disk_space_before = keyspace.disk_space();
+    let start = Instant::now();
+
+    let mut compacted = Vec::new();
+    for registered in registrations.values() {
+        if compacted.contains(&&registered.partition_id) {
+            continue;
+        }
+        registered
+            .open_partition(keyspace)
+            .major_compact()
+            .expect("Failed to compact partition");
+        compacted.push(&registered.partition_id);
+    }
+
+    CompactionReport {
+        disk_space_before,
+        disk_space_after: keyspace.disk_space(),
+        duration: start.elapsed(),
+    }
+}
+
+// ===== Immediate Writes =====
+
+/// Writes a component value for an entity right away, without waiting for the save
+/// systems. The write is visible to every read that starts after this returns, see
+/// [`DatabaseReader::get_persisted`].
+///
+/// Like any other save, a [`DatabaseChange`] message is sent for the write, or a
+/// [`DatabaseError::Serialization`] if the format can't encode the value. A batched
+/// write of the same record that is still pending is dropped, so it can't overwrite
+/// this one when the batch is committed. Types with a TTL get their write time stamped
+/// along with the record, so the TTL counts from this write.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_save_now_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+/// app.update();
+///
+/// // Persist a checkpoint before anything else happens
+/// let entity = app.world_mut().spawn_empty().id();
+/// save_type_now(app.world_mut(), entity, &Score(100));
+///
+/// let reader = app.world().resource::<DatabaseReader>();
+/// assert_eq!(reader.get_persisted::<Score>(entity).map(|score| score.0), Some(100));
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+pub fn save_type_now<T: Serialize + for<'de> Deserialize<'de> + Component>(
+    world: &mut World,
+    entity: Entity,
+    value: &T,
+) {
+    let registered = world.resource::<DatabaseRegistrations>().registered::<T>();
+    let partition = registered.open_partition(world.resource::<KeyspaceWrapper>());
+    let codec = world_codec::<T>(world);
+    let bytes = match persist_component(None, &partition, registered, codec, entity, value) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            world.write_message(error);
+            return;
+        }
+    };
+    let batch = world.get_resource::<DatabaseWriteBatch>();
+    if let Some(batch) = batch {
+        batch.discard(&partition, &registered.key(entity));
+    }
+    if registered.ttl.is_some() {
+        let timestamps =
+            registered.open_side_partition(world.resource::<KeyspaceWrapper>(), "saved");
+        insert_record(
+            None,
+            &timestamps,
+            registered.key(entity),
+            unix_nanos().to_be_bytes().into(),
+        );
+        if let Some(batch) = batch {
+            batch.discard(&timestamps, &registered.key(entity));
+        }
+    }
+    if world.contains_resource::<DatabaseWriteCounts>() {
+        let keyspace = world.resource::<KeyspaceWrapper>();
+        // Counts aren't read back right away, they can wait for the batch
+        count_write(
+            batch,
+            &registered.open_side_partition(keyspace, "writes"),
+            registered.key(entity),
+        );
+    }
+
+    world.write_message(DatabaseChange {
+        entity,
+        type_name: std::any::type_name::<T>(),
+        bytes,
+    });
+}
+
+/// Writes `bytes` as the `T` record of an entity, without serializing anything, so tests
+/// can exercise how malformed records are handled.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_inject_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+/// app.update();
+///
+/// let entity = app.world_mut().spawn_empty().id();
+/// inject_raw_record::<Score>(app.world(), entity, 7u32.to_le_bytes());
+///
+/// let reader = app.world().resource::<DatabaseReader>();
+/// assert_eq!(reader.get_persisted::<Score>(entity).map(|score| score.0), Some(7));
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+#[cfg(feature = "test-utils")]
+pub fn inject_raw_record<T: Component>(world: &World, entity: Entity, bytes: impl Into<Vec<u8>>) {
+    let registered = tooling_registration::<T>(world);
+
+    registered
+        .open_partition(world.resource::<KeyspaceWrapper>())
+        .insert(registered.key(entity), bytes.into())
+        .expect("Failed to insert into database");
+}
+
+/// Runs raw `(key, value)` pairs through the record decoding the load of `T` uses,
+/// returning the entity and component each one decodes to or why it was rejected. The
+/// load reports rejected records with a [`DatabaseError::Deserialization`] and skips
+/// them. Nothing is read from or written to the database, so it
+/// works without one being open.
+///
+/// Meant as the entry point of `cargo-fuzz` targets hardening the load against
+/// malformed data: any panic is a bug. The world must come from an app with the plugin
+/// added and `T` registered, and can be reused across runs.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// let mut app = App::new();
+/// app.add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+///
+/// let results = fuzz_load_records::<Score>(
+///     app.world(),
+///     [
+///         (&3u32.to_be_bytes()[..], &7u32.to_le_bytes()[..]),
+///         (&[0xff][..], &7u32.to_le_bytes()[..]),
+///         (&3u32.to_be_bytes()[..], &[1][..]),
+///     ],
+/// );
+/// assert_eq!(results[0].as_ref().map(|(entity, score)| (entity.index(), score.0)).ok(), Some((3, 7)));
+/// assert!(results[1].is_err());
+/// assert!(results[2].is_err());
+/// ```
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_load_records<'a, T: Serialize + for<'de> Deserialize<'de> + Component>(
+    world: &World,
+    records: impl IntoIterator<Item = (&'a [u8], &'a [u8])>,
+) -> Vec<Result<(Entity, T), FormatError>> {
+    let registered = tooling_registration::<T>(world);
+    let codec = world_codec::<T>(world);
 
-/// Main plugin struct for database functionality
-pub struct DatabasePlugin;
+    records
+        .into_iter()
+        .map(|(key, value)| decode_record(registered, codec, key, value))
+        .collect()
+}
 
-impl Plugin for DatabasePlugin {
-    fn build(&self, app: &mut App) {
-        // Initialize database early
-        app.add_systems(PreStartup, setup_database);
-        app.add_systems(PostUpdate, cleanup_update_markers);
-        app.init_resource::<DatabaseLoadMapper>();
-    }
+/// Returns the registration of `T` for the test and fuzzing entry points, which are
+/// often handed a hand-built world, with a panic naming what's missing
+#[cfg_attr(
+    not(any(feature = "test-utils", feature = "fuzzing")),
+    allow(dead_code)
+)]
+fn tooling_registration<T: Component>(world: &World) -> &RegisteredType {
+    let type_name = std::any::type_name::<T>();
+    world
+        .get_resource::<DatabaseRegistrations>()
+        .unwrap_or_else(|| panic!("Add the DatabasePlugin before using {type_name} records"))
+        .get(&TypeId::of::<T>())
+        .unwrap_or_else(|| panic!("{type_name} is not registered for persistence"))
 }
 
-// ===== Component Markers =====
+// ===== Blobs =====
 
-/// Marker component indicating the entity was just updated from the database
-#[derive(Component)]
-pub struct DatabaseJustUpdated;
+/// Name of the partition holding every blob
+const BLOB_PARTITION: &str = "blobs";
 
-/// Marker component to exclude an entity from database operations
-#[derive(Component)]
-pub struct DatabaseIgnore;
+/// Blobs are split into chunks of this many bytes, keeping single values small
+const BLOB_CHUNK_SIZE: usize = 64 * 1024;
 
-// ===== Database Setup and Management =====
+/// Stores a binary payload (a generated texture, an audio buffer, ...) for an entity
+/// under `name`, replacing any previous blob with that name.
+///
+/// Blobs skip serde entirely and live in a partition of their own, split into 64 KiB
+/// chunks that are written in one atomic batch. They aren't tied to any component, so
+/// they stay in the database until [`remove_blob`] is called.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_blob_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin);
+/// app.update();
+///
+/// let entity = app.world_mut().spawn_empty().id();
+/// let texture = vec![7u8; 200 * 1024];
+/// set_blob(app.world(), entity, "texture", &texture);
+///
+/// assert_eq!(get_blob(app.world(), entity, "texture"), Some(texture));
+/// assert_eq!(get_blob(app.world(), entity, "audio"), None);
+///
+/// remove_blob(app.world(), entity, "texture");
+/// assert_eq!(get_blob(app.world(), entity, "texture"), None);
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+pub fn set_blob(world: &World, entity: Entity, name: &str, bytes: &[u8]) {
+    let keyspace = world.resource::<KeyspaceWrapper>();
+    let partition = open_blob_partition(keyspace);
+    let prefix = blob_prefix(entity, name);
 
-/// Initializes the database connection and creates the KeyspaceWrapper resource
-fn setup_database(mut commands: Commands, database_location: Option<Res<DatabaseLocation>>) {
-    let database_location = database_location
-        .map(|a| a.0.clone())
-        .unwrap_or("./database".to_string());
+    let mut batch = keyspace.batch();
+    remove_blob_chunks(&mut batch, &partition, &prefix);
+    // An empty blob still gets a chunk, so it can be told apart from a missing one
+    let chunks = bytes
+        .chunks(BLOB_CHUNK_SIZE)
+        .chain(bytes.is_empty().then_some(bytes));
+    for (index, chunk) in chunks.enumerate() {
+        let key = [prefix.as_slice(), &(index as u32).to_be_bytes()].concat();
+        batch.insert(&partition, key, chunk);
+    }
+    batch.commit().expect("Failed to commit blob");
+}
 
-    let keyspace = Config::new(database_location)
-        .open()
-        .expect("Failed to open database keyspace");
+/// Reads the blob an entity stores under `name`, see [`set_blob`]
+pub fn get_blob(world: &World, entity: Entity, name: &str) -> Option<Vec<u8>> {
+    let partition = open_blob_partition(world.resource::<KeyspaceWrapper>());
 
-    commands.insert_resource(KeyspaceWrapper(keyspace));
+    // Read from a snapshot, so a concurrent set_blob can't mix old and new chunks
+    let mut chunks = partition
+        .snapshot()
+        .prefix(blob_prefix(entity, name))
+        .peekable();
+    chunks.peek()?;
+    Some(
+        chunks
+            .flat_map(|chunk| chunk.expect("Failed to read from database").1.to_vec())
+            .collect(),
+    )
 }
 
-/// Removes DatabaseJustUpdated markers after database operations
-fn cleanup_update_markers(mut commands: Commands, query: Query<Entity, With<DatabaseJustUpdated>>) {
-    for entity in query.iter() {
-        commands.entity(entity).remove::<DatabaseJustUpdated>();
+/// Deletes the blob an entity stores under `name`, see [`set_blob`]
+pub fn remove_blob(world: &World, entity: Entity, name: &str) {
+    let keyspace = world.resource::<KeyspaceWrapper>();
+    let partition = open_blob_partition(keyspace);
+
+    let mut batch = keyspace.batch();
+    remove_blob_chunks(&mut batch, &partition, &blob_prefix(entity, name));
+    batch.commit().expect("Failed to remove blob");
+}
+
+/// Adds the removal of every chunk of a blob to a batch
+fn remove_blob_chunks(batch: &mut fjall::Batch, partition: &PartitionHandle, prefix: &[u8]) {
+    for chunk in partition.prefix(prefix) {
+        let (key, _) = chunk.expect("Failed to read from database");
+        batch.remove(partition, key);
     }
 }
 
-// ===== Component Persistence Trait =====
+/// Opens the partition holding every blob
+fn open_blob_partition(keyspace: &Keyspace) -> PartitionHandle {
+    keyspace
+        .open_partition(BLOB_PARTITION, PartitionCreateOptions::default())
+        .expect("Failed to open partition")
+}
 
-/// Trait to add database mapping capabilities for components
-pub trait AddDatabaseMapping {
-    /// Adds database persistence for a component type. This will automatically handle
-    /// saving and loading the component to/from the database.
+/// Prefix of the chunk keys of a blob: the entity index, then the length-prefixed name
+fn blob_prefix(entity: Entity, name: &str) -> Vec<u8> {
+    [
+        &entity.index().to_be_bytes()[..],
+        &(name.len() as u32).to_be_bytes(),
+        name.as_bytes(),
+    ]
+    .concat()
+}
+
+// ===== Manual Persistence =====
+
+/// Opens the database, like the plugin does in [`PreStartup`].
+///
+/// Together with [`load_type`], [`save_type`] and [`finish_database_tick`], this lets a
+/// custom loop drive persistence itself, e.g. a headless server stepping its world at
+/// a fixed rate without bevy's runner. The plugin must still be added, and types
+/// registered, on the app the world came from.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Position(pub i32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_manual_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Position>();
+///
+/// let world = app.world_mut();
+/// open_database(world);
+/// load_type::<Position>(world);
+/// finish_database_tick(world);
+///
+/// let entity = world.spawn(Position(0)).id();
+/// for _ in 0..3 {
+///     world.get_mut::<Position>(entity).unwrap().0 += 1;
+///     save_type::<Position>(world);
+///     finish_database_tick(world);
+///     world.clear_trackers();
+/// }
+///
+/// let reader = world.resource::<DatabaseReader>();
+/// assert_eq!(reader.get_persisted::<Position>(entity).map(|p| p.0), Some(3));
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+pub fn open_database(world: &mut World) {
+    world
+        .run_system_cached(setup_database)
+        .expect("Failed to open database");
+}
+
+/// Loads every record of `T` into the world, like the startup load does. Lazy types
+/// only get their placeholders.
+pub fn load_type<T: Component>(world: &mut World) {
+    let load = world
+        .resource::<DatabaseRegistrations>()
+        .registered::<T>()
+        .load;
+    load(world);
+}
+
+/// Saves the `T` components that changed since the last call and deletes the records of
+/// removed ones, the way `T` was registered: types added with
+/// [`AddDatabaseMapping::add_database_mapping_on_add`] only save new components, capped
+/// and expiring types evict records, and relationships save their targets.
+///
+/// Removals are read from bevy's removed component buffers, which the custom loop must
+/// keep rotating with [`World::clear_trackers`] once per tick.
+pub fn save_type<T: Component>(world: &mut World) {
+    let save = world
+        .resource::<DatabaseRegistrations>()
+        .registered::<T>()
+        .save;
+    save(world);
+}
+
+/// Ends a persistence tick: clears the markers that keep freshly loaded components from
+/// being saved again and commits the write batch, if writes are batched
+pub fn finish_database_tick(world: &mut World) {
+    world
+        .run_system_cached(cleanup_update_markers)
+        .expect("Failed to run cleanup system");
+    world
+        .run_system_cached(commit_write_batch)
+        .expect("Failed to commit write batch");
+    world
+        .run_system_cached(reset_serialization_time)
+        .expect("Failed to reset serialization time budget");
+}
+
+// ===== Reading Persisted Data =====
+
+/// Cheap, thread-safe handle for reading persisted components outside of systems,
+/// for example from tasks running on bevy's async task pools.
+///
+/// The handle is inserted as a resource when the database is opened, clone it out
+/// of `Res<DatabaseReader>` to move it into a task. Only types registered with
+/// [`AddDatabaseMapping`] can be read, other types are treated as having no records.
+///
+/// # Consistency
+/// Reads see every write that completed before they started, including writes made
+/// by the save systems while a task is running. [`DatabaseReader::iter_persisted`]
+/// iterates over a snapshot taken when it is called, so writes happening during the
+/// iteration are not observed. Since the save systems write once per frame, a read
+/// racing with them may see some components of a frame's changes but not others.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_reader_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+/// app.update();
+///
+/// let entity = app.world_mut().spawn(Score(42)).id();
+/// app.update();
+///
+/// let reader = app.world().resource::<DatabaseReader>().clone();
+/// let score = std::thread::spawn(move || reader.get_persisted::<Score>(entity))
+///     .join()
+///     .unwrap();
+/// assert_eq!(score.map(|score| score.0), Some(42));
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+#[derive(Resource, Clone)]
+pub struct DatabaseReader {
+    keyspace: Keyspace,
+    registrations: Arc<HashMap<TypeId, RegisteredType>>,
+    formats: Arc<DatabaseFormats>,
+}
+
+impl DatabaseReader {
+    /// Reads the persisted value of `T` for an entity, if there is one.
     ///
-    /// # Type Parameters
-    /// * `T`: Component type that implements Serialize, Deserialize, and Component
+    /// Point reads go through fjall's write buffer, so they always return the latest
+    /// completed write, including ones made by [`save_type_now`] moments earlier. A
+    /// record that can't be decoded reads as missing.
+    pub fn get_persisted<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &self,
+        entity: Entity,
+    ) -> Option<T> {
+        let codec = self.formats.codec::<T>();
+        let registered = self.registrations.get(&TypeId::of::<T>())?;
+
+        registered
+            .open_partition(&self.keyspace)
+            .get(registered.key(entity))
+            .expect("Failed to read from database")
+            .and_then(|value| (codec.deserialize)(value.as_ref()).ok())
+    }
+
+    /// Number of times the `T` record of an entity has been written while
+    /// [`DatabaseWriteCounts`] was set, `0` if it never was.
     ///
     /// # Example
     /// ```
@@ -89,131 +3681,1155 @@ pub trait AddDatabaseMapping {
     /// use bevy_easy_database::*;
     ///
     /// #[derive(Component, serde::Serialize, serde::Deserialize)]
-    /// pub struct Player(pub String);
+    /// pub struct Position(pub i32);
+    ///
+    /// let location = std::env::temp_dir().join("bevy_easy_database_write_count_doc");
+    /// # let _ = std::fs::remove_dir_all(&location);
+    /// let mut app = App::new();
+    /// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+    ///     .insert_resource(DatabaseWriteCounts)
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Position>();
+    /// app.update();
+    ///
+    /// let entity = app.world_mut().spawn(Position(0)).id();
+    /// for _ in 0..3 {
+    ///     app.update();
+    ///     app.world_mut().get_mut::<Position>(entity).unwrap().0 += 1;
+    /// }
+    /// app.update();
+    ///
+    /// let reader = app.world().resource::<DatabaseReader>();
+    /// assert_eq!(reader.write_count::<Position>(entity), 4);
+    /// # drop(app);
+    /// # std::fs::remove_dir_all(&location).unwrap();
+    /// ```
+    pub fn write_count<T: Component>(&self, entity: Entity) -> u64 {
+        let Some(registered) = self.registrations.get(&TypeId::of::<T>()) else {
+            return 0;
+        };
+
+        read_u64(
+            &registered.open_side_partition(&self.keyspace, "writes"),
+            registered.key(entity),
+        )
+    }
+
+    /// Iterates over every persisted value of `T`, along with the entity it was saved
+    /// for. Records that can't be decoded are skipped.
+    pub fn iter_persisted<T: Serialize + for<'de> Deserialize<'de> + Component>(
+        &self,
+    ) -> impl Iterator<Item = (Entity, T)> {
+        let codec = self.formats.codec::<T>();
+        let registered = self.registrations.get(&TypeId::of::<T>()).cloned();
+        let keyspace = self.keyspace.clone();
+
+        registered.into_iter().flat_map(move |registered| {
+            let records = registered
+                .open_partition(&keyspace)
+                .snapshot()
+                .prefix(registered.record_prefix());
+
+            records.filter_map(move |record| {
+                let (key, value) = record.ok()?;
+                decode_record(&registered, codec, &key, &value).ok()
+            })
+        })
+    }
+}
+
+// ===== Staged Loading =====
+
+impl DatabaseReader {
+    /// Loads every registered type into a new, detached world.
+    ///
+    /// Loading happens entirely outside the main world, so it can run on a background
+    /// task while the game keeps running. Once it's done, [`apply_staging_world`] moves
+    /// the loaded entities into the main world in one go, so no frame ever observes a
+    /// half-loaded save.
+    ///
+    /// # Example
+    /// ```
+    /// use bevy_app::prelude::*;
+    /// use bevy_ecs::prelude::*;
+    /// use bevy_easy_database::*;
     ///
     /// #[derive(Component, serde::Serialize, serde::Deserialize)]
     /// pub struct Score(pub u32);
     ///
-    /// fn main() {
-    ///     App::new()
-    ///         //...
-    ///         .add_plugins(DatabasePlugin)
-    ///         // Register as many components as you need
-    ///         .add_database_mapping::<Player>()
-    ///         .add_database_mapping::<Score>()
-    ///         .run();
-    /// }
+    /// let location = std::env::temp_dir().join("bevy_easy_database_staging_doc");
+    /// # let _ = std::fs::remove_dir_all(&location);
+    /// let mut app = App::new();
+    /// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+    ///     .add_plugins(DatabasePlugin)
+    ///     .add_database_mapping::<Score>();
+    /// app.update();
+    /// app.world_mut().spawn(Score(3));
+    /// app.update();
+    ///
+    /// let reader = app.world().resource::<DatabaseReader>().clone();
+    /// let staging = std::thread::spawn(move || reader.load_staging_world())
+    ///     .join()
+    ///     .unwrap();
+    ///
+    /// let loaded = apply_staging_world(app.world_mut(), staging);
+    /// assert_eq!(loaded.len(), 1);
+    /// assert_eq!(app.world().get::<Score>(loaded[0]).unwrap().0, 3);
+    /// # drop(app);
+    /// # std::fs::remove_dir_all(&location).unwrap();
     /// ```
-    fn add_database_mapping<T: Serialize + for<'de> Deserialize<'de> + Component>(
-        &mut self,
-    ) -> &mut Self;
+    pub fn load_staging_world(&self) -> World {
+        let registrations = DatabaseRegistrations((*self.registrations).clone());
+
+        let mut staging = World::new();
+        staging.insert_resource(KeyspaceWrapper(self.keyspace.clone()));
+        staging.insert_resource((*self.formats).clone());
+        staging.init_resource::<DatabaseLoadMapper>();
+        staging.init_resource::<SerializationTimeSpent>();
+        staging.init_resource::<Messages<DatabaseError>>();
+        staging.insert_resource(registrations.clone());
+
+        for registered in registrations.in_load_order() {
+            (registered.load)(&mut staging);
+        }
+        staging
+    }
 }
 
-impl AddDatabaseMapping for App {
-    fn add_database_mapping<T: Serialize + for<'de> Deserialize<'de> + Component + Any>(
-        &mut self,
-    ) -> &mut Self {
-        // Add system for loading components from database on startup
-        self.add_systems(Startup, load_components::<T>);
+/// Moves every entity loaded into a staging world (see [`DatabaseReader::load_staging_world`])
+/// into `world`, returning the new entities.
+///
+/// The moved entities are tracked by the [`DatabaseLoadMapper`], marked with
+/// [`DatabaseJustUpdated`] and get the bundle set with
+/// [`AddDatabaseMapping::set_load_spawn_bundle`], exactly as if they had been loaded
+/// directly. [`DatabaseLazy`] placeholders are moved too, so [`load_lazy_component`]
+/// works on the moved entities. Entities already in `world` are left alone, despawn
+/// them first to replace the current state.
+/// Components are moved by serializing them, so any [`Entity`] values stored inside
+/// components are not remapped, except for the targets of relationships registered with
+/// [`AddDatabaseMapping::add_database_relationship`].
+pub fn apply_staging_world(world: &mut World, mut staging: World) -> Vec<Entity> {
+    // Records the staged load skipped are reported in the main world
+    if let Some(mut errors) = staging.get_resource_mut::<Messages<DatabaseError>>() {
+        let errors: Vec<_> = errors.drain().collect();
+        world.write_message_batch(errors);
+    }
 
-        // Add system for saving component changes during runtime
-        self.add_systems(Update, save_component_changes::<T>);
+    let staged: Vec<_> = staging.resource::<DatabaseLoadMapper>().iter().collect();
+    let lazy: Vec<LazyHooks> = staging
+        .resource::<DatabaseRegistrations>()
+        .values()
+        .filter_map(|registered| registered.lazy)
+        .collect();
 
-        // Add system for handling component removal
-        self.add_systems(Update, handle_component_removal::<T>);
+    let mut moved = HashMap::with_capacity(staged.len());
+    let mut loaded = Vec::with_capacity(staged.len());
+    for (database_entity, staged_entity) in staged {
+        // Entities that can't make the trip are reported and left behind
+        let blob = match snapshot_entity(&staging, staged_entity) {
+            Ok(Some(blob)) => blob,
+            Ok(None) => continue,
+            Err(error) => {
+                world.write_message(error);
+                continue;
+            }
+        };
+        let entity = match spawn_blob_components(world, &blob) {
+            Ok(entity) => entity,
+            Err((type_name, error)) => {
+                world.write_message(DatabaseError::Deserialization {
+                    type_name,
+                    key: database_entity.index().to_be_bytes().to_vec(),
+                    reason: error.to_string(),
+                });
+                continue;
+            }
+        };
+        let mut entity_mut = world.entity_mut(entity);
+        entity_mut.insert(DatabaseJustUpdated);
+        // Placeholders aren't persisted, so they're carried over on their own
+        for lazy in &lazy {
+            if (lazy.contains)(&staging, staged_entity) {
+                (lazy.insert)(&mut entity_mut);
+            }
+        }
+        world
+            .resource_mut::<DatabaseLoadMapper>()
+            .insert(database_entity, entity);
+        moved.insert(staged_entity, entity);
+        loaded.push(entity);
+    }
 
-        self
+    // Entities spawned by a staged load get the same extra components as loaded ones
+    if world.contains_resource::<DatabaseSpawnBundle>() {
+        world.resource_scope(|world, spawn_bundle: Mut<DatabaseSpawnBundle>| {
+            let mut commands = world.commands();
+            for &entity in &loaded {
+                (spawn_bundle.0)(&mut commands.entity(entity));
+            }
+        });
+        world.flush();
+    }
+
+    // Relationships point at staged entities, so they're moved once every target exists
+    let relationships: Vec<RelationshipHooks> = staging
+        .resource::<DatabaseRegistrations>()
+        .values()
+        .filter_map(|registered| registered.relationship)
+        .collect();
+    for (&staged_entity, &entity) in &moved {
+        for relationship in &relationships {
+            let target = (relationship.target)(&staging, staged_entity)
+                .and_then(|target| moved.get(&target));
+            if let Some(&target) = target {
+                (relationship.insert)(&mut world.entity_mut(entity), target);
+            }
+        }
     }
+    loaded
 }
 
-// ===== Database Operations =====
+/// Discards the current state of every persisted entity and loads it again from the
+/// database, the "revert to last save" operation.
+///
+/// Entities that were loaded or have a registered component are despawned, unless they
+/// are marked with [`DatabaseIgnore`]. Then the [`DatabaseLoadMapper`] is cleared and
+/// every registered type is loaded again. Changes the save systems haven't written yet
+/// are lost, a pending write batch is committed first.
+///
+/// The despawns must not delete the records about to be reloaded, so the removal
+/// buffers are cleared twice with [`World::clear_trackers`]. Other systems won't see
+/// any component removals that happened earlier in the frame either.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_reload_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+/// app.update();
+/// let entity = app.world_mut().spawn(Score(1)).id();
+/// app.update();
+///
+/// // Throw away an unsaved change
+/// app.world_mut().get_mut::<Score>(entity).unwrap().0 = 99;
+/// reload_world(app.world_mut());
+/// app.update();
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+pub fn reload_world(world: &mut World) {
+    if let Some(batch) = world.get_resource::<DatabaseWriteBatch>() {
+        batch.commit(world.resource::<KeyspaceWrapper>());
+    }
 
-/// Loads components from the database during startup
-fn load_components<T: Serialize + for<'de> Deserialize<'de> + Component>(
-    mut commands: Commands,
-    mut database_load_mapper: ResMut<DatabaseLoadMapper>,
-    keyspace: Res<KeyspaceWrapper>,
-) {
-    let partition_id = get_type_partition_id::<T>();
-    let partition = keyspace
-        .open_partition(&partition_id, PartitionCreateOptions::default())
-        .expect("Failed to open partition");
+    let registrations = world.resource::<DatabaseRegistrations>().clone();
+    let loaded: HashSet<Entity> = world
+        .resource::<DatabaseLoadMapper>()
+        .iter()
+        .map(|(_, live)| live)
+        .collect();
+    let managed: Vec<Entity> = world
+        .query_filtered::<EntityRef, Without<DatabaseIgnore>>()
+        .iter(world)
+        .filter(|entity| {
+            loaded.contains(&entity.id())
+                || registrations
+                    .keys()
+                    .any(|&type_id| entity.contains_type_id(type_id))
+        })
+        .map(|entity| entity.id())
+        .collect();
 
-    for record in partition.iter() {
-        let Ok((key, value)) = record else { continue };
+    for entity in managed {
+        world.despawn(entity);
+    }
+    world.clear_trackers();
+    world.clear_trackers();
+
+    world.resource_mut::<DatabaseLoadMapper>().clear();
+    world
+        .run_system_cached(spawn_in_creation_order)
+        .expect("Failed to run load system");
+    for registered in registrations.in_load_order() {
+        (registered.load)(world);
+    }
+}
+
+// ===== Streaming Export =====
+
+/// Marks the start of a database exported with [`export_database`]
+const DATABASE_EXPORT_MAGIC: &[u8; 4] = b"BEDB";
 
-        // Convert key bytes to entity ID
-        // I know this is weird dunno how to do it differently though
-        let mut bytes = [0; 4];
-        for (i, byte) in key.as_ref().iter().enumerate() {
-            bytes[i] = *byte;
-        }
-        
-        if let Some(database_entity) = Entity::from_raw_u32(u32::from_be_bytes(bytes)) {
-            // Deserialize and insert component
-            let component =
-                bincode::deserialize::<T>(value.as_ref()).expect("Failed to deserialize component");
-
-            match database_load_mapper.0.get(&database_entity).cloned() {
-                None => {
-                    let entity = commands.spawn((component, DatabaseJustUpdated));
-                    database_load_mapper.insert(database_entity, entity.id());
+/// Version of the [`export_database`] format, bumped on incompatible changes
+const DATABASE_EXPORT_VERSION: u16 = 1;
+
+/// Streams every record of every registered type to `writer`, for backups or syncing a
+/// save somewhere else. Records are written as they're read from a snapshot of each
+/// type, so memory use stays bounded no matter how big the database is.
+///
+/// The export starts with a magic number and a format version. Each record follows as
+/// a `1` byte, then the type name, entity index and value, names and values prefixed
+/// with their length as big-endian `u32`. A `0` byte ends the export. Records are
+/// identified by type name rather than partition, so an export can be imported into a
+/// database that lays its types out differently.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_export_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+/// app.update();
+///
+/// let mut backup = Vec::new();
+/// export_database(app.world(), &mut backup).unwrap();
+/// import_database(app.world(), backup.as_slice()).unwrap();
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+pub fn export_database(world: &World, mut writer: impl Write) -> std::io::Result<()> {
+    let keyspace = world.resource::<KeyspaceWrapper>();
+
+    writer.write_all(DATABASE_EXPORT_MAGIC)?;
+    writer.write_all(&DATABASE_EXPORT_VERSION.to_be_bytes())?;
+
+    for registered in world.resource::<DatabaseRegistrations>().values() {
+        let records = registered
+            .open_partition(keyspace)
+            .snapshot()
+            .prefix(registered.record_prefix());
+
+        for record in records {
+            let (key, value) = record.map_err(std::io::Error::other)?;
+            let Some(entity) = registered.entity(&key) else {
+                continue;
+            };
+
+            writer.write_all(&[1])?;
+            write_framed(&mut writer, registered.type_name.as_bytes())?;
+            writer.write_all(&entity.index().to_be_bytes())?;
+            write_framed(&mut writer, &value)?;
+        }
+    }
+
+    writer.write_all(&[0])?;
+    writer.flush()
+}
+
+/// Writes the records of an export made with [`export_database`] into the database, one
+/// at a time as they're read, returning how many were imported.
+///
+/// Records of types that aren't registered are skipped. Existing records with the same
+/// entity are overwritten. Imported records aren't loaded into the world, use
+/// [`reload_world`] or restart to see them. An export that is truncated or malformed
+/// fails with [`std::io::ErrorKind::InvalidData`] or
+/// [`std::io::ErrorKind::UnexpectedEof`], keeping the records imported so far.
+pub fn import_database(world: &World, mut reader: impl Read) -> std::io::Result<usize> {
+    let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+    let keyspace = world.resource::<KeyspaceWrapper>();
+    let registrations = world.resource::<DatabaseRegistrations>();
+
+    let mut header = [0; 6];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != DATABASE_EXPORT_MAGIC {
+        return Err(invalid("Not a database export"));
+    }
+    if u16::from_be_bytes([header[4], header[5]]) != DATABASE_EXPORT_VERSION {
+        return Err(invalid("Unsupported database export version"));
+    }
+
+    let mut imported = 0;
+    loop {
+        let mut tag = [0];
+        reader.read_exact(&mut tag)?;
+        match tag[0] {
+            0 => return Ok(imported),
+            1 => {}
+            _ => return Err(invalid("Malformed database export")),
+        }
+
+        let type_name = read_framed(&mut reader)?;
+        let mut index = [0; 4];
+        reader.read_exact(&mut index)?;
+        let value = read_framed(&mut reader)?;
+
+        let entity =
+            entity_from_key(&index).ok_or_else(|| invalid("Invalid entity in database export"))?;
+        let Some(registered) = registrations
+            .values()
+            .find(|registered| registered.type_name.as_bytes() == type_name)
+        else {
+            continue;
+        };
+
+        registered
+            .open_partition(keyspace)
+            .insert(registered.key(entity), value)
+            .map_err(std::io::Error::other)?;
+        imported += 1;
+    }
+}
+
+/// Writes bytes prefixed with their length as a big-endian `u32`
+fn write_framed(writer: &mut impl Write, bytes: &[u8]) -> std::io::Result<()> {
+    writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    writer.write_all(bytes)
+}
+
+/// Reads bytes written by [`write_framed`]
+fn read_framed(reader: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let mut bytes = Vec::new();
+    reader
+        .take(u32::from_be_bytes(len).into())
+        .read_to_end(&mut bytes)?;
+    if bytes.len() != u32::from_be_bytes(len) as usize {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+// ===== Merging Databases =====
+
+/// How [`merge_database`] resolves records that exist in both databases with different values
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep the record of the active database
+    KeepLocal,
+    /// Take the record of the other database
+    KeepOther,
+    /// Take whichever record was last written most recently. Only types registered
+    /// with [`AddDatabaseMapping::add_database_mapping_ttl`] store last write times, for
+    /// other types the local record is kept and the type is listed in
+    /// [`MergeReport::untimed_types`].
+    Newest,
+}
+
+/// A record present in both databases with different values
+#[derive(Clone, Debug)]
+pub struct MergeConflict {
+    /// Type of the conflicting record
+    pub type_name: &'static str,
+    /// Entity the record was saved for
+    pub entity: Entity,
+    /// Whether the record of the other database won
+    pub took_other: bool,
+}
+
+/// What [`merge_database`] did, or would do for a dry run
+#[derive(Clone, Debug, Default)]
+pub struct MergeReport {
+    /// Records only present in the other database, copied over
+    pub added: usize,
+    /// Records present in both databases with different values
+    pub conflicts: Vec<MergeConflict>,
+    /// Types with conflicts that [`MergeStrategy::Newest`] couldn't resolve because
+    /// they store no last write times, their local records were kept
+    pub untimed_types: Vec<&'static str>,
+}
+
+/// Merges the records of every registered type from the database at `other_path` into
+/// the active one, e.g. to import a region of a friend's world.
+///
+/// Records only found in the other database are copied, conflicting records are
+/// resolved with `strategy`. All writes are applied in one atomic batch, a `dry_run`
+/// applies nothing and only reports what would happen. Merged records are not loaded
+/// into the world, they show up the next time the database is loaded.
+///
+/// The other database must not be open anywhere else. Fails with an
+/// [`std::io::ErrorKind::NotFound`] IO error if there is no database at `other_path`.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_merge_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+/// app.update();
+///
+/// // Preview what merging a friend's world would change
+/// let friend = std::env::temp_dir().join("bevy_easy_database_merge_doc_friend");
+/// if let Ok(report) = merge_database(app.world(), &friend, MergeStrategy::KeepLocal, true) {
+///     println!("{} new records, {} conflicts", report.added, report.conflicts.len());
+/// }
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+pub fn merge_database(
+    world: &World,
+    other_path: impl AsRef<Path>,
+    strategy: MergeStrategy,
+    dry_run: bool,
+) -> Result<MergeReport, fjall::Error> {
+    let keyspace = world.resource::<KeyspaceWrapper>();
+    let other_path = other_path.as_ref();
+    // Opening a missing keyspace would create an empty one
+    if !other_path.join("version").is_file() {
+        return Err(fjall::Error::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No database at {}", other_path.display()),
+        )));
+    }
+    let other = Config::new(other_path).open()?;
+    let mut batch = keyspace.batch();
+    let mut report = MergeReport::default();
+
+    // Partitions missing locally are only created when something is written to them
+    let local_partition = |name: &str| {
+        (!dry_run || keyspace.partition_exists(name)).then(|| {
+            keyspace
+                .open_partition(name, PartitionCreateOptions::default())
+                .expect("Failed to open partition")
+        })
+    };
+    let other_partition = |name: &str| {
+        other.partition_exists(name).then(|| {
+            other
+                .open_partition(name, PartitionCreateOptions::default())
+                .expect("Failed to open partition")
+        })
+    };
+
+    for registered in world.resource::<DatabaseRegistrations>().values() {
+        let Some(records) = other_partition(&registered.partition_id) else {
+            continue;
+        };
+        let local = local_partition(&registered.partition_id);
+        // Side partitions with the times records were last ("saved") and first
+        // ("written") written, copied along with the records that are taken
+        let side_partitions: Vec<_> = ["saved", "written"]
+            .into_iter()
+            .filter_map(|name| {
+                let other = other_partition(&registered.side_partition_id(name))?;
+                Some((other, local_partition(&registered.side_partition_id(name))))
+            })
+            .collect();
+        let last_written = |keyspace: &Keyspace| {
+            keyspace
+                .partition_exists(&registered.side_partition_id("saved"))
+                .then(|| registered.open_side_partition(keyspace, "saved"))
+        };
+        let (local_saved, other_saved) = (last_written(keyspace), last_written(&other));
+        let timed = local_saved.is_some() || other_saved.is_some();
+        let saved_at = |saved: &Option<PartitionHandle>, key: &[u8]| {
+            saved.as_ref().map_or(0, |saved| read_u64(saved, key))
+        };
+
+        for record in records.prefix(registered.record_prefix()) {
+            let (key, value) = record?;
+            let Some(entity) = registered.entity(&key) else {
+                continue;
+            };
+
+            let local_value = match &local {
+                Some(local) => local.get(&key)?,
+                None => None,
+            };
+            match local_value {
+                None => report.added += 1,
+                Some(local_value) if local_value == value => continue,
+                Some(_) => {
+                    let took_other = match strategy {
+                        MergeStrategy::KeepLocal => false,
+                        MergeStrategy::KeepOther => true,
+                        MergeStrategy::Newest if !timed => {
+                            if !report.untimed_types.contains(&registered.type_name) {
+                                report.untimed_types.push(registered.type_name);
+                            }
+                            false
+                        }
+                        MergeStrategy::Newest => {
+                            saved_at(&other_saved, &key) > saved_at(&local_saved, &key)
+                        }
+                    };
+                    report.conflicts.push(MergeConflict {
+                        type_name: registered.type_name,
+                        entity,
+                        took_other,
+                    });
+                    if !took_other {
+                        continue;
+                    }
                 }
-                Some(entity) => {
-                    commands.entity(entity).insert((component, DatabaseJustUpdated));
+            }
+
+            if dry_run {
+                continue;
+            }
+            for (other_side, local_side) in &side_partitions {
+                if let (Some(time), Some(local_side)) = (other_side.get(&key)?, local_side) {
+                    batch.insert(local_side, key.clone(), time);
                 }
             }
+            if let Some(local) = &local {
+                batch.insert(local, key, value);
+            }
         }
     }
+
+    if !dry_run {
+        batch.commit()?;
+    }
+
+    Ok(report)
 }
 
-/// Saves changed components to the database
-fn save_component_changes<T: Serialize + Component>(
-    keyspace: Res<KeyspaceWrapper>,
-    query: Query<
-        (Entity, &T),
-        (
-            Changed<T>,
-            (Without<DatabaseJustUpdated>, Without<DatabaseIgnore>),
-        ),
-    >,
-) {
-    let partition_id = get_type_partition_id::<T>();
-    let partition = keyspace
-        .open_partition(&partition_id, PartitionCreateOptions::default())
-        .expect("Failed to open partition");
+// ===== Entity Snapshots =====
 
-    for (entity, component) in query.iter() {
-        let serialized = bincode::serialize(&component).expect("Failed to serialize component");
+/// Every registered component of a single entity, serialized.
+///
+/// Created with [`snapshot_entity`] and turned back into an entity with
+/// [`spawn_from_blob`]. Components are identified by type name, so a blob can be
+/// spawned into any world that registered the same types.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EntityBlob {
+    components: Vec<(String, Vec<u8>)>,
+}
 
-        partition
-            .insert(entity.index().to_be_bytes(), serialized)
-            .expect("Failed to insert into database");
+/// Captures all registered components of an entity, without touching the database.
+/// Relationships are left out, their targets only make sense in this world.
+///
+/// Returns `None` if the entity doesn't exist, and a [`DatabaseError::Serialization`]
+/// if the format of one of its components can't encode it.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// let mut app = App::new();
+/// app.add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+///
+/// let original = app.world_mut().spawn(Score(7)).id();
+/// let blob = snapshot_entity(app.world(), original).unwrap().unwrap();
+/// let copy = spawn_from_blob(app.world_mut(), &blob).unwrap();
+///
+/// assert_ne!(original, copy);
+/// assert_eq!(app.world().get::<Score>(copy).unwrap().0, 7);
+/// ```
+pub fn snapshot_entity(world: &World, entity: Entity) -> Result<Option<EntityBlob>, DatabaseError> {
+    if world.get_entity(entity).is_err() {
+        return Ok(None);
     }
+
+    let mut components = Vec::new();
+    for registered in world
+        .get_resource::<DatabaseRegistrations>()
+        .into_iter()
+        .flat_map(|registrations| registrations.values())
+    {
+        if let Some(bytes) = (registered.serialize)(world, entity)? {
+            components.push((registered.type_name.to_string(), bytes));
+        }
+    }
+
+    Ok(Some(EntityBlob { components }))
 }
 
-/// Handles removal of components from the database
-fn handle_component_removal<T: Component>(
-    keyspace: Res<KeyspaceWrapper>,
-    mut removed: RemovedComponents<T>,
-) {
-    let partition_id = get_type_partition_id::<T>();
-    let partition = keyspace
-        .open_partition(&partition_id, PartitionCreateOptions::default())
-        .expect("Failed to open partition");
+/// Spawns a new entity from a blob created by [`snapshot_entity`].
+///
+/// Components whose type isn't registered in this world are skipped. Fails without
+/// spawning anything if a component can't be deserialized. The new entity is persisted
+/// like any other freshly spawned entity.
+pub fn spawn_from_blob(world: &mut World, blob: &EntityBlob) -> Result<Entity, FormatError> {
+    spawn_blob_components(world, blob)
+        .map_err(|(type_name, error)| format!("Failed to decode {type_name}: {error}").into())
+}
 
-    for entity in removed.read() {
-        partition
-            .remove(entity.index().to_be_bytes())
-            .expect("Failed to remove from database");
+/// Spawns an entity with the components of a blob, despawning it again and returning
+/// the type that failed if one can't be deserialized
+fn spawn_blob_components(
+    world: &mut World,
+    blob: &EntityBlob,
+) -> Result<Entity, (&'static str, FormatError)> {
+    let registrations = world
+        .get_resource::<DatabaseRegistrations>()
+        .cloned()
+        .unwrap_or_default();
+
+    let mut entity = world.spawn_empty();
+    for (type_name, bytes) in &blob.components {
+        let registered = registrations
+            .values()
+            .find(|registered| registered.type_name == type_name);
+        if let Some(registered) = registered {
+            if let Err(error) = (registered.insert)(&mut entity, bytes) {
+                entity.despawn();
+                return Err((registered.type_name, error));
+            }
+        }
+    }
+    Ok(entity.id())
+}
+
+/// Marks the start of an entity exported with [`export_entity`]
+const EXPORT_MAGIC: &[u8; 4] = b"BEDE";
+
+/// Version of the [`export_entity`] format, bumped on incompatible changes
+const EXPORT_VERSION: u16 = 1;
+
+/// Exports all registered components of an entity as a self-contained byte buffer, for
+/// sharing a single object (a custom ship, a house) between players.
+///
+/// The buffer starts with a magic number and a format version, followed by the
+/// [`EntityBlob`] of the entity. Components are encoded with their type's
+/// [`DatabaseFormat`], so both sides must use the same formats. [`Entity`] values stored
+/// inside components are exported as is and aren't remapped on import. Relationships
+/// registered with [`AddDatabaseMapping::add_database_relationship`] aren't exported.
+///
+/// Returns `None` if the entity doesn't exist, and a [`DatabaseError::Serialization`]
+/// if the format of one of its components can't encode it.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Hull(pub String);
+///
+/// let mut app = App::new();
+/// app.add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Hull>();
+///
+/// let ship = app.world_mut().spawn(Hull("steel".into())).id();
+/// let shared = export_entity(app.world(), ship).unwrap().unwrap();
+///
+/// let imported = import_entity(app.world_mut(), &shared).unwrap();
+/// assert_eq!(app.world().get::<Hull>(imported).unwrap().0, "steel");
+/// assert!(import_entity(app.world_mut(), b"not an entity").is_err());
+/// ```
+pub fn export_entity(world: &World, entity: Entity) -> Result<Option<Vec<u8>>, DatabaseError> {
+    let Some(blob) = snapshot_entity(world, entity)? else {
+        return Ok(None);
+    };
+
+    let mut bytes = EXPORT_MAGIC.to_vec();
+    bytes.extend_from_slice(&EXPORT_VERSION.to_be_bytes());
+    bincode::serialize_into(&mut bytes, &blob).expect("Failed to serialize entity");
+    Ok(Some(bytes))
+}
+
+/// Spawns an entity exported with [`export_entity`], returning it.
+///
+/// Components whose type isn't registered in this world are skipped. Fails without
+/// spawning anything if the buffer isn't an export of a supported version or a
+/// component can't be deserialized.
+pub fn import_entity(world: &mut World, bytes: &[u8]) -> Result<Entity, FormatError> {
+    let bytes = bytes
+        .strip_prefix(EXPORT_MAGIC)
+        .ok_or("Not an exported entity")?;
+    let (version, blob) = bytes.split_at_checked(2).ok_or("Truncated entity export")?;
+    let version = u16::from_be_bytes([version[0], version[1]]);
+    if version != EXPORT_VERSION {
+        return Err(format!("Unsupported entity export version {version}").into());
     }
+    let blob: EntityBlob = bincode::deserialize(blob)?;
+
+    spawn_from_blob(world, &blob)
+}
+
+// ===== On-disk Layout =====
+
+/// Returns the name of the fjall partition `T` is stored in by default, for tools that
+/// read the database directory directly.
+///
+/// This is part of the on-disk contract:
+/// - Each type gets its own partition, named by this function, unless it was moved
+///   into a shared partition with [`AddDatabaseMapping::set_database_partition`].
+/// - A record key is the type's key prefix (see
+///   [`AddDatabaseMapping::add_database_mapping_with_key_prefix`]), then the type tag
+///   as a big-endian `u64` when the partition is shared, then the entity index as a
+///   big-endian `u32`.
+/// - A record value is the component encoded with the type's [`DatabaseFormat`].
+///
+/// The name is derived from the type's `TypeId`, which is only guaranteed to be the
+/// same for builds made with the same compiler. Use [`registered_partition_id`] to
+/// get the partition a type is actually stored in.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_partition_id_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+/// app.update();
+/// app.world_mut().spawn(Score(3));
+/// app.update();
+///
+/// let keyspace = app.world().resource::<KeyspaceWrapper>();
+/// assert!(keyspace.partition_exists(&partition_id::<Score>()));
+/// assert_eq!(
+///     registered_partition_id(app.world(), std::any::type_name::<Score>()),
+///     Some(partition_id::<Score>()),
+/// );
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+pub fn partition_id<T: Any>() -> String {
+    format!("{}", get_type_tag::<T>())
+}
+
+/// Returns the name of the partition a registered type is stored in, looked up by its
+/// [`std::any::type_name`]. Unlike [`partition_id`] this accounts for shared partitions.
+///
+/// Returns `None` if no registered type has that name.
+pub fn registered_partition_id(world: &World, type_name: &str) -> Option<String> {
+    world
+        .resource::<DatabaseRegistrations>()
+        .values()
+        .find(|registered| registered.type_name == type_name)
+        .map(|registered| registered.partition_id.clone())
+}
+
+/// Returns the partition handle `T`'s records are stored in, the same one the crate
+/// reads and writes. An escape hatch for fjall features the crate doesn't wrap, like
+/// custom range scans or prefix deletes.
+///
+/// Keys and values follow the layout documented on [`partition_id`]. Writes made
+/// through the handle bypass change tracking, [`DatabaseBatchWrites`] and
+/// [`DatabaseChange`] messages, and are only picked up by entities on the next load.
+///
+/// # Panics
+/// Panics if `T` isn't registered for persistence or the database isn't open yet.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Score(pub u32);
+///
+/// let location = std::env::temp_dir().join("bevy_easy_database_raw_partition_doc");
+/// # let _ = std::fs::remove_dir_all(&location);
+/// let mut app = App::new();
+/// app.insert_resource(DatabaseLocation(location.to_string_lossy().into_owned()))
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Score>();
+/// app.update();
+/// let first = app.world_mut().spawn(Score(1)).id();
+/// app.world_mut().spawn(Score(2));
+/// app.update();
+///
+/// // Entity indices are stored big-endian, so a range scan visits them in order
+/// let partition = raw_partition::<Score>(app.world());
+/// let from = first.index().to_be_bytes();
+/// assert_eq!(partition.range(from..).count(), 2);
+/// # drop(app);
+/// # std::fs::remove_dir_all(&location).unwrap();
+/// ```
+pub fn raw_partition<T: Component>(world: &World) -> PartitionHandle {
+    world
+        .resource::<DatabaseRegistrations>()
+        .registered::<T>()
+        .open_partition(world.resource::<KeyspaceWrapper>())
 }
 
 // ===== Utility Functions =====
 
-/// Generates a unique partition ID for a given type
-fn get_type_partition_id<T: Any>() -> String {
+/// Records a component type as persisted so it can be looked up at runtime
+fn register_type<T: Serialize + for<'de> Deserialize<'de> + Component>(app: &mut App) {
+    app.world_mut()
+        .get_resource_or_init::<DatabaseRegistrations>()
+        .insert(
+            TypeId::of::<T>(),
+            RegisteredType {
+                type_name: std::any::type_name::<T>(),
+                partition_id: partition_id::<T>(),
+                key_prefix: Vec::new(),
+                type_tag: None,
+                serialize: |world, entity| {
+                    let codec = world_codec::<T>(world);
+                    world
+                        .get::<T>(entity)
+                        .map(|component| {
+                            (codec.serialize)(component).map_err(|error| {
+                                DatabaseError::Serialization {
+                                    entity,
+                                    type_name: std::any::type_name::<T>(),
+                                    reason: error.to_string(),
+                                }
+                            })
+                        })
+                        .transpose()
+                },
+                insert: |entity, bytes| {
+                    let codec = world_codec::<T>(entity.world());
+                    entity.insert((codec.deserialize)(bytes)?);
+                    Ok(())
+                },
+                load: |world| {
+                    world
+                        .run_system_cached(load_components::<T>)
+                        .expect("Failed to run load system");
+                },
+                save: save_changes::<T, Changed<T>>,
+                load_after: Vec::new(),
+                lazy: None,
+                relationship: None,
+                ttl: None,
+            },
+        );
+
+    // Entities are spawned in creation order first, when enabled
+    app.configure_sets(Startup, load_set::<T>().after(spawn_in_creation_order));
+}
+
+/// Gives access to the registration of a type, to adjust it after [`register_type`]
+fn registration_mut<T: Component>(app: &mut App) -> Mut<'_, RegisteredType> {
+    app.world_mut()
+        .resource_mut::<DatabaseRegistrations>()
+        .map_unchanged(|registrations| {
+            registrations
+                .get_mut(&TypeId::of::<T>())
+                .expect("Component type is not registered for persistence")
+        })
+}
+
+/// Runs the save systems of a type saved with the change filter `F`
+fn save_changes<T: Serialize + for<'de> Deserialize<'de> + Component, F: QueryFilter + 'static>(
+    world: &mut World,
+) {
+    world
+        .run_system_cached(save_component_changes::<T, F>)
+        .expect("Failed to run save system");
+    world
+        .run_system_cached(handle_component_removal::<T>)
+        .expect("Failed to run removal system");
+}
+
+/// Records a relationship type as persisted, stored as the target entity's bits
+fn register_relationship<R: Relationship>(app: &mut App) {
+    app.world_mut()
+        .get_resource_or_init::<DatabaseRegistrations>()
+        .insert(
+            TypeId::of::<R>(),
+            RegisteredType {
+                type_name: std::any::type_name::<R>(),
+                partition_id: partition_id::<R>(),
+                key_prefix: Vec::new(),
+                type_tag: None,
+                // Targets are meaningless outside their world, so snapshots leave them out
+                serialize: |_, _| Ok(None),
+                insert: |_, _| Err("Relationships can't be restored from a snapshot".into()),
+                load: |world| {
+                    world
+                        .run_system_cached(load_relationships::<R>)
+                        .expect("Failed to run load system");
+                },
+                save: |world| {
+                    world
+                        .run_system_cached(save_relationship_changes::<R>)
+                        .expect("Failed to run save system");
+                    world
+                        .run_system_cached(handle_component_removal::<R>)
+                        .expect("Failed to run removal system");
+                },
+                load_after: Vec::new(),
+                lazy: None,
+                relationship: Some(RelationshipHooks {
+                    target: |world, entity| world.get::<R>(entity).map(R::get),
+                    insert: |entity, target| {
+                        entity.insert(R::from(target));
+                    },
+                }),
+                ttl: None,
+            },
+        );
+
+    app.configure_sets(Startup, load_set::<R>().after(spawn_in_creation_order));
+}
+
+/// Returns the per-user data directory of the current platform, the one
+/// `directories::BaseDirs::data_dir` returns in a usual environment. Reading the few variables involved here avoids
+/// depending on that crate (and its platform-specific dependency tree) for one path.
+fn platform_data_dir() -> Option<PathBuf> {
+    let env_path = |name| std::env::var_os(name).map(PathBuf::from);
+
+    if cfg!(target_os = "windows") {
+        env_path("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        Some(env_path("HOME")?.join("Library/Application Support"))
+    } else {
+        env_path("XDG_DATA_HOME")
+            .filter(|path| path.is_absolute())
+            .or_else(|| Some(env_path("HOME")?.join(".local/share")))
+    }
+}
+
+/// Decodes a stored record of a type into the entity it was saved for and its value.
+/// Shared by every read of records, so the load and [`fuzz_load_records`] can't drift.
+fn decode_record<T>(
+    registered: &RegisteredType,
+    codec: Codec<T>,
+    key: &[u8],
+    value: &[u8],
+) -> Result<(Entity, T), FormatError> {
+    let entity = registered
+        .entity(key)
+        .ok_or("Key isn't a record key of this type")?;
+    Ok((entity, (codec.deserialize)(value)?))
+}
+
+/// Converts a record key back into the entity it was saved for
+fn entity_from_key(key: &[u8]) -> Option<Entity> {
+    let bytes = <[u8; 4]>::try_from(key).ok()?;
+    Entity::from_raw_u32(u32::from_be_bytes(bytes))
+}
+
+/// Serializes a component and writes it under the entity's key, returning the stored
+/// bytes. Nothing is written if the format can't encode the component.
+fn persist_component<T>(
+    batch: Option<&DatabaseWriteBatch>,
+    partition: &PartitionHandle,
+    registered: &RegisteredType,
+    codec: Codec<T>,
+    entity: Entity,
+    component: &T,
+) -> Result<fjall::Slice, DatabaseError> {
+    let serialized =
+        (codec.serialize)(component).map_err(|error| DatabaseError::Serialization {
+            entity,
+            type_name: registered.type_name,
+            reason: error.to_string(),
+        })?;
+    let serialized = fjall::Slice::from(serialized);
+
+    insert_record(batch, partition, registered.key(entity), serialized.clone());
+
+    Ok(serialized)
+}
+
+/// Current time in nanoseconds since the unix epoch, as stored in timestamp partitions
+fn unix_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
+
+/// Reads a big-endian `u64` stored in a side partition, `0` if there is none
+fn read_u64(partition: &PartitionHandle, key: impl AsRef<[u8]>) -> u64 {
+    decode_u64(partition.get(key).expect("Failed to read from database"))
+}
+
+/// Reads a key, seeing writes still waiting in the write batch
+fn read_record(
+    batch: Option<&DatabaseWriteBatch>,
+    partition: &PartitionHandle,
+    key: &[u8],
+) -> Option<fjall::Slice> {
+    match batch {
+        Some(batch) => batch.read(partition, key),
+        None => partition.get(key).expect("Failed to read from database"),
+    }
+}
+
+/// Decodes a big-endian `u64` value, `0` if there is none
+fn decode_u64(bytes: Option<fjall::Slice>) -> u64 {
+    bytes
+        .and_then(|bytes| <[u8; 8]>::try_from(bytes.as_ref()).ok())
+        .map_or(0, u64::from_be_bytes)
+}
+
+/// Increments the write count stored under a record key
+fn count_write(batch: Option<&DatabaseWriteBatch>, write_counts: &PartitionHandle, key: Vec<u8>) {
+    // Earlier increments may still be waiting in the write batch
+    let count = decode_u64(read_record(batch, write_counts, &key));
+
+    insert_record(batch, write_counts, key, (count + 1).to_be_bytes().into());
+}
+
+/// Hashes the reflected structure of a type, stable across builds and compiler versions
+fn schema_hash(type_info: &TypeInfo) -> u64 {
+    // The type's own path is left out, moving it to another module doesn't change records
+    let mut description = Vec::new();
+    match type_info {
+        TypeInfo::Struct(info) => description.extend(
+            info.iter()
+                .map(|field| format!("{}: {}", field.name(), field.type_path())),
+        ),
+        TypeInfo::TupleStruct(info) => {
+            description.extend(info.iter().map(|field| field.type_path().to_string()))
+        }
+        TypeInfo::Enum(info) => description.extend(info.iter().map(|variant| match variant {
+            VariantInfo::Struct(variant) => {
+                let fields: Vec<_> = variant
+                    .iter()
+                    .map(|field| format!("{}: {}", field.name(), field.type_path()))
+                    .collect();
+                format!("{} {{ {} }}", variant.name(), fields.join(", "))
+            }
+            VariantInfo::Tuple(variant) => {
+                let fields: Vec<_> = variant.iter().map(|field| field.type_path()).collect();
+                format!("{}({})", variant.name(), fields.join(", "))
+            }
+            VariantInfo::Unit(variant) => variant.name().to_string(),
+        })),
+        other => description.push(other.type_path().to_string()),
+    }
+
+    // FNV-1a, unlike std's hashers its output is guaranteed to never change
+    description
+        .join("\n")
+        .bytes()
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// Generates a unique tag for a given type
+fn get_type_tag<T: Any>() -> u64 {
     let mut hasher = DefaultHasher::new();
     TypeId::of::<T>().hash(&mut hasher);
-    format!("{}", hasher.finish())
+    hasher.finish()
 }
+
+#[cfg(test)]
+mod tests;
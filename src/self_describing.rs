@@ -0,0 +1,660 @@
+//! The bundled [`SelfDescribing`] format, which stores field names along with the values
+
+use crate::{DatabaseFormat, FormatError};
+use serde::{Deserialize, Serialize};
+
+/// Self-describing format that stores field names along with the values, for types
+/// that are expected to gain fields over time.
+///
+/// Values are converted into a tree of maps, sequences and primitives, which is then
+/// encoded with bincode. Structs are stored as maps keyed by field name, so records
+/// written before a field was added load with the field's `#[serde(default)]`, and
+/// fields that were removed since are ignored. Records are bigger than with
+/// [`Bincode`](crate::Bincode) since every field name is stored in every record.
+///
+/// # Example
+/// ```
+/// use bevy_app::prelude::*;
+/// use bevy_ecs::prelude::*;
+/// use bevy_easy_database::*;
+///
+/// /// The inventory as it was saved by an older version of the game
+/// #[derive(serde::Serialize)]
+/// pub struct OldInventory {
+///     gold: u32,
+/// }
+///
+/// #[derive(Component, serde::Serialize, serde::Deserialize)]
+/// pub struct Inventory {
+///     gold: u32,
+///     #[serde(default)]
+///     gems: u32,
+/// }
+///
+/// App::new()
+///     .add_plugins(DatabasePlugin)
+///     .add_database_mapping::<Inventory>()
+///     .set_database_format::<Inventory, SelfDescribing>();
+///
+/// let old = SelfDescribing::serialize(&OldInventory { gold: 5 }).unwrap();
+/// let inventory: Inventory = SelfDescribing::deserialize(&old).unwrap();
+/// assert_eq!((inventory.gold, inventory.gems), (5, 0));
+/// ```
+pub struct SelfDescribing;
+
+impl DatabaseFormat for SelfDescribing {
+    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, FormatError> {
+        let value = value.serialize(ValueSerializer)?;
+        Ok(bincode::serialize(&value)?)
+    }
+
+    fn deserialize<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T, FormatError> {
+        let value: Value = bincode::deserialize(bytes)?;
+        Ok(T::deserialize(value)?)
+    }
+}
+
+/// Any serde value, as stored by [`SelfDescribing`]. Enum variants are stored by name,
+/// as a string for unit variants and a single entry map for the others.
+#[derive(Serialize, Deserialize)]
+enum Value {
+    Unit,
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    I128(i128),
+    U128(u128),
+    F32(f32),
+    F64(f64),
+    Char(char),
+    String(String),
+    Bytes(Vec<u8>),
+    None,
+    Some(Box<Value>),
+    Seq(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+}
+
+/// Error of converting to and from [`Value`]
+type ValueError = serde::de::value::Error;
+
+/// Converts anything serializable into a [`Value`]
+struct ValueSerializer;
+
+impl serde::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = ValueError;
+    type SerializeSeq = SerializeValueSeq;
+    type SerializeTuple = SerializeValueSeq;
+    type SerializeTupleStruct = SerializeValueSeq;
+    type SerializeTupleVariant = SerializeValueSeq;
+    type SerializeMap = SerializeValueMap;
+    type SerializeStruct = SerializeValueMap;
+    type SerializeStructVariant = SerializeValueMap;
+
+    fn serialize_bool(self, value: bool) -> Result<Value, ValueError> {
+        Ok(Value::Bool(value))
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Value, ValueError> {
+        Ok(Value::I64(value.into()))
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Value, ValueError> {
+        Ok(Value::I64(value.into()))
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Value, ValueError> {
+        Ok(Value::I64(value.into()))
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Value, ValueError> {
+        Ok(Value::I64(value))
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<Value, ValueError> {
+        Ok(Value::I128(value))
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Value, ValueError> {
+        Ok(Value::U64(value.into()))
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Value, ValueError> {
+        Ok(Value::U64(value.into()))
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Value, ValueError> {
+        Ok(Value::U64(value.into()))
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Value, ValueError> {
+        Ok(Value::U64(value))
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<Value, ValueError> {
+        Ok(Value::U128(value))
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Value, ValueError> {
+        Ok(Value::F32(value))
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Value, ValueError> {
+        Ok(Value::F64(value))
+    }
+
+    fn serialize_char(self, value: char) -> Result<Value, ValueError> {
+        Ok(Value::Char(value))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Value, ValueError> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Value, ValueError> {
+        Ok(Value::Bytes(value.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value, ValueError> {
+        Ok(Value::None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, ValueError> {
+        Ok(Value::Some(Box::new(value.serialize(self)?)))
+    }
+
+    fn serialize_unit(self) -> Result<Value, ValueError> {
+        Ok(Value::Unit)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, ValueError> {
+        Ok(Value::Unit)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value, ValueError> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, ValueError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, ValueError> {
+        Ok(Value::Map(vec![(
+            Value::String(variant.to_string()),
+            value.serialize(self)?,
+        )]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeValueSeq, ValueError> {
+        Ok(SerializeValueSeq {
+            variant: None,
+            items: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeValueSeq, ValueError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeValueSeq, ValueError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeValueSeq, ValueError> {
+        Ok(SerializeValueSeq {
+            variant: Some(variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeValueMap, ValueError> {
+        Ok(SerializeValueMap {
+            variant: None,
+            entries: Vec::with_capacity(len.unwrap_or_default()),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeValueMap, ValueError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeValueMap, ValueError> {
+        Ok(SerializeValueMap {
+            variant: Some(variant),
+            entries: Vec::with_capacity(len),
+            key: None,
+        })
+    }
+}
+
+/// Wraps the value of an enum variant into a single entry map keyed by its name
+fn variant_value(variant: Option<&'static str>, value: Value) -> Value {
+    match variant {
+        Some(variant) => Value::Map(vec![(Value::String(variant.to_string()), value)]),
+        None => value,
+    }
+}
+
+/// Collects sequences, tuples and tuple variants into a [`Value::Seq`]
+struct SerializeValueSeq {
+    variant: Option<&'static str>,
+    items: Vec<Value>,
+}
+
+impl serde::ser::SerializeSeq for SerializeValueSeq {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ValueError> {
+        self.items.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        Ok(variant_value(self.variant, Value::Seq(self.items)))
+    }
+}
+
+impl serde::ser::SerializeTuple for SerializeValueSeq {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ValueError> {
+        serde::ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        serde::ser::SerializeSeq::end(self)
+    }
+}
+
+impl serde::ser::SerializeTupleStruct for SerializeValueSeq {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ValueError> {
+        serde::ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        serde::ser::SerializeSeq::end(self)
+    }
+}
+
+impl serde::ser::SerializeTupleVariant for SerializeValueSeq {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ValueError> {
+        serde::ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        serde::ser::SerializeSeq::end(self)
+    }
+}
+
+/// Collects maps, structs and struct variants into a [`Value::Map`]
+struct SerializeValueMap {
+    variant: Option<&'static str>,
+    entries: Vec<(Value, Value)>,
+    /// Key waiting for its value
+    key: Option<Value>,
+}
+
+impl serde::ser::SerializeMap for SerializeValueMap {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), ValueError> {
+        self.key = Some(key.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ValueError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| serde::ser::Error::custom("map value without a key"))?;
+        self.entries.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        Ok(variant_value(self.variant, Value::Map(self.entries)))
+    }
+}
+
+impl serde::ser::SerializeStruct for SerializeValueMap {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ValueError> {
+        self.entries.push((
+            Value::String(key.to_string()),
+            value.serialize(ValueSerializer)?,
+        ));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        serde::ser::SerializeMap::end(self)
+    }
+}
+
+impl serde::ser::SerializeStructVariant for SerializeValueMap {
+    type Ok = Value;
+    type Error = ValueError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ValueError> {
+        serde::ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Value, ValueError> {
+        serde::ser::SerializeMap::end(self)
+    }
+}
+
+impl<'de> serde::Deserializer<'de> for Value {
+    type Error = ValueError;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        use serde::de::value::{MapDeserializer, SeqDeserializer};
+
+        match self {
+            Value::Unit => visitor.visit_unit(),
+            Value::Bool(value) => visitor.visit_bool(value),
+            Value::I64(value) => visitor.visit_i64(value),
+            Value::U64(value) => visitor.visit_u64(value),
+            Value::I128(value) => visitor.visit_i128(value),
+            Value::U128(value) => visitor.visit_u128(value),
+            Value::F32(value) => visitor.visit_f32(value),
+            Value::F64(value) => visitor.visit_f64(value),
+            Value::Char(value) => visitor.visit_char(value),
+            Value::String(value) => visitor.visit_string(value),
+            Value::Bytes(value) => visitor.visit_byte_buf(value),
+            Value::None => visitor.visit_none(),
+            Value::Some(value) => visitor.visit_some(*value),
+            Value::Seq(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Map(entries) => {
+                let mut map = MapDeserializer::new(entries.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_newtype_struct<V: serde::de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: serde::de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        use serde::de::IntoDeserializer;
+
+        match self {
+            Value::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Value::Map(mut entries) if entries.len() == 1 => {
+                let (variant, value) = entries.remove(0);
+                visitor.visit_enum(ValueVariant { variant, value })
+            }
+            _ => Err(serde::de::Error::custom("expected an enum variant")),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+        byte_buf option unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+impl<'de> serde::de::IntoDeserializer<'de, ValueError> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// An enum variant carrying data, stored as a single entry map
+struct ValueVariant {
+    variant: Value,
+    value: Value,
+}
+
+impl<'de> serde::de::EnumAccess<'de> for ValueVariant {
+    type Error = ValueError;
+    type Variant = Value;
+
+    fn variant_seed<S: serde::de::DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Value), ValueError> {
+        Ok((seed.deserialize(self.variant)?, self.value))
+    }
+}
+
+impl<'de> serde::de::VariantAccess<'de> for Value {
+    type Error = ValueError;
+
+    fn unit_variant(self) -> Result<(), ValueError> {
+        Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<S: serde::de::DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<S::Value, ValueError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: serde::de::Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        serde::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: serde::de::Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        serde::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, HashMap};
+    use std::fmt::Debug;
+
+    /// Asserts that `value` reads back unchanged
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de> + PartialEq + Debug>(value: T) {
+        let bytes = SelfDescribing::serialize(&value).unwrap();
+        assert_eq!(SelfDescribing::deserialize::<T>(&bytes).unwrap(), value);
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Shape {
+        Empty,
+        Circle(f32),
+        Rect(u32, u32),
+        Polygon { sides: u8, regular: bool },
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Meters(f64);
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Marker;
+
+    #[test]
+    fn primitives() {
+        round_trip(true);
+        round_trip(i8::MIN);
+        round_trip(u16::MAX);
+        round_trip(i64::MIN);
+        round_trip(u64::MAX);
+        round_trip(i128::MIN);
+        round_trip(u128::MAX);
+        round_trip(1.5f32);
+        round_trip(-2.25f64);
+        round_trip('ß');
+        round_trip(String::from("text"));
+        round_trip(());
+    }
+
+    #[test]
+    fn enums() {
+        round_trip(Shape::Empty);
+        round_trip(Shape::Circle(1.0));
+        round_trip(Shape::Rect(2, 3));
+        round_trip(Shape::Polygon {
+            sides: 6,
+            regular: true,
+        });
+        round_trip(vec![Shape::Empty, Shape::Rect(0, 0)]);
+    }
+
+    #[test]
+    fn options() {
+        round_trip(None::<u32>);
+        round_trip(Some(7u32));
+        round_trip(Some(None::<u32>));
+        round_trip(Some(Some(Shape::Empty)));
+    }
+
+    #[test]
+    fn maps() {
+        round_trip(HashMap::from([
+            (1u32, "one".to_string()),
+            (2, "two".into()),
+        ]));
+        round_trip(BTreeMap::from([
+            ("circle".to_string(), Shape::Circle(1.0)),
+            ("empty".into(), Shape::Empty),
+        ]));
+        round_trip(BTreeMap::from([((1u8, 2u8), vec![Some(3u8)])]));
+    }
+
+    #[test]
+    fn newtypes_and_unit_structs() {
+        round_trip(Meters(3.5));
+        round_trip(Some(Meters(0.0)));
+        round_trip(Marker);
+    }
+
+    #[test]
+    fn sequences_and_tuples() {
+        round_trip(vec![1u8, 2, 3]);
+        round_trip((1u8, "two".to_string(), Some(3i32)));
+        round_trip([u64::MAX; 4]);
+        round_trip(Vec::<u32>::new());
+    }
+
+    #[test]
+    fn fields_can_be_added_and_removed() {
+        #[derive(Serialize)]
+        struct Old {
+            kept: u32,
+            removed: String,
+        }
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct New {
+            kept: u32,
+            #[serde(default)]
+            added: Option<u32>,
+        }
+
+        let bytes = SelfDescribing::serialize(&Old {
+            kept: 1,
+            removed: "gone".into(),
+        })
+        .unwrap();
+        assert_eq!(
+            SelfDescribing::deserialize::<New>(&bytes).unwrap(),
+            New {
+                kept: 1,
+                added: None
+            }
+        );
+    }
+
+    #[test]
+    fn mismatched_records_are_errors() {
+        let bytes = SelfDescribing::serialize(&"Hexagon").unwrap();
+        assert!(SelfDescribing::deserialize::<Shape>(&bytes).is_err());
+
+        let bytes = SelfDescribing::serialize(&Shape::Rect(1, 2)).unwrap();
+        assert!(SelfDescribing::deserialize::<u32>(&bytes).is_err());
+
+        assert!(SelfDescribing::deserialize::<u32>(&[0xff; 3]).is_err());
+    }
+}
@@ -0,0 +1,1099 @@
+//! Regression tests, each running against a database of its own
+
+use super::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Database directory unique to a test, deleted when dropped
+struct TestLocation(PathBuf);
+
+impl TestLocation {
+    fn new(name: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "bevy_easy_database_test_{name}_{}_{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        TestLocation(path)
+    }
+
+    /// Builds an app on this database, lets `setup` register its types and runs startup
+    fn app(&self, setup: impl FnOnce(&mut App)) -> App {
+        let mut app = App::new();
+        app.insert_resource(DatabaseLocation(self.0.to_string_lossy().into_owned()))
+            .add_plugins(DatabasePlugin);
+        setup(&mut app);
+        app.update();
+        app
+    }
+}
+
+impl Drop for TestLocation {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Every `M` message sent so far
+fn messages<M: Message + Clone>(app: &App) -> Vec<M> {
+    let messages = app.world().resource::<Messages<M>>();
+    messages.get_cursor().read(messages).cloned().collect()
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Score(u32);
+
+#[test]
+fn corrupt_records_are_reported_and_skipped() {
+    let location = TestLocation::new("corrupt");
+    let setup = |app: &mut App| {
+        app.add_database_mapping::<Score>();
+    };
+    let corrupt = Entity::from_raw_u32(1000).unwrap();
+    {
+        let mut app = location.app(setup);
+        app.world_mut().spawn(Score(1));
+        app.update();
+        raw_partition::<Score>(app.world())
+            .insert(corrupt.index().to_be_bytes(), [1])
+            .unwrap();
+    }
+
+    let mut app = location.app(setup);
+    let scores: Vec<_> = app
+        .world_mut()
+        .query::<&Score>()
+        .iter(app.world())
+        .cloned()
+        .collect();
+    assert_eq!(scores, [Score(1)]);
+    assert!(matches!(
+        messages::<DatabaseError>(&app)[..],
+        [DatabaseError::Deserialization { .. }]
+    ));
+
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(reader.get_persisted::<Score>(corrupt), None);
+    assert_eq!(reader.iter_persisted::<Score>().count(), 1);
+
+    // Staged loads skip it too and report it in the main world
+    let reader = reader.clone();
+    let loaded = apply_staging_world(app.world_mut(), reader.load_staging_world());
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(messages::<DatabaseError>(&app).len(), 2);
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Terrain(Vec<u8>);
+
+#[test]
+fn staged_loads_keep_lazy_placeholders() {
+    let location = TestLocation::new("staged_lazy");
+    let mut app = location.app(|app| {
+        app.add_database_mapping::<Score>()
+            .add_database_mapping_lazy::<Terrain>();
+    });
+    app.world_mut().spawn((Score(1), Terrain(vec![1, 2, 3])));
+    app.update();
+
+    let reader = app.world().resource::<DatabaseReader>().clone();
+    let loaded = apply_staging_world(app.world_mut(), reader.load_staging_world());
+    let [entity] = loaded[..] else {
+        panic!("Expected one staged entity, got {loaded:?}");
+    };
+    assert_eq!(app.world().get::<Score>(entity), Some(&Score(1)));
+    assert!(app.world().get::<Terrain>(entity).is_none());
+    assert!(app.world().get::<DatabaseLazy<Terrain>>(entity).is_some());
+
+    app.world_mut()
+        .commands()
+        .entity(entity)
+        .queue(load_lazy_component::<Terrain>);
+    app.world_mut().flush();
+    assert_eq!(
+        app.world().get::<Terrain>(entity),
+        Some(&Terrain(vec![1, 2, 3]))
+    );
+}
+
+#[test]
+fn replacement_swaps_in_the_side_database() {
+    let location = TestLocation::new("replace");
+    let side = TestLocation::new("replace_side");
+    let mut app = location.app(|app| {
+        app.add_database_mapping::<Score>();
+    });
+    {
+        let side_keyspace = Config::new(&side.0).open().unwrap();
+        let saves = side_keyspace
+            .open_partition("saves", PartitionCreateOptions::default())
+            .unwrap();
+        saves.insert("slot", "complete").unwrap();
+    }
+
+    // A mistyped path fails before anything moves, rather than swapping in an empty database
+    let missing = location.0.with_extension("missing");
+    let error = atomic_replace_database(app.world_mut(), &missing).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    assert!(!missing.exists());
+
+    atomic_replace_database(app.world_mut(), &side.0).unwrap();
+    let keyspace = app.world().resource::<KeyspaceWrapper>();
+    let saves = keyspace
+        .open_partition("saves", PartitionCreateOptions::default())
+        .unwrap();
+    assert_eq!(
+        saves.get("slot").unwrap().as_deref(),
+        Some(&b"complete"[..])
+    );
+    assert!(!side.0.exists());
+    assert!(!replaced_path(&location.0).exists());
+
+    // The save systems write into the new database
+    let entity = app.world_mut().spawn(Score(1)).id();
+    app.update();
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(reader.get_persisted::<Score>(entity), Some(Score(1)));
+}
+
+#[test]
+fn failed_replacement_keeps_the_database_open() {
+    let location = TestLocation::new("failed_replace");
+    let setup = |app: &mut App| {
+        app.add_database_mapping::<Score>();
+    };
+    let mut app = location.app(setup);
+
+    // The side database lives inside the active one, so it's gone once that's moved aside
+    let side = location.0.join("side");
+    Config::new(&side).open().unwrap();
+    assert!(atomic_replace_database(app.world_mut(), &side).is_err());
+
+    // Leftovers that can't be cleaned up fail the replacement before anything moves
+    std::fs::write(replaced_path(&location.0), "").unwrap();
+    assert!(atomic_replace_database(app.world_mut(), &side).is_err());
+
+    let entity = app.world_mut().spawn(Score(4)).id();
+    app.update();
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(reader.get_persisted::<Score>(entity), Some(Score(4)));
+    let _ = std::fs::remove_file(replaced_path(&location.0));
+}
+
+#[derive(Component, bevy_reflect::Reflect, Serialize, Deserialize, PartialEq, Debug)]
+struct Health {
+    current: u32,
+    max: u32,
+}
+
+#[test]
+fn schema_migrations_run_before_the_load() {
+    let location = TestLocation::new("schema_migration");
+    let setup = |app: &mut App| {
+        app.add_database_mapping::<Health>()
+            .add_database_schema_migration::<Health>(|bytes| {
+                let current: u32 = Bincode::deserialize(bytes)?;
+                Bincode::serialize(&Health { current, max: 100 })
+            });
+    };
+    let entity = Entity::from_raw_u32(1000).unwrap();
+    {
+        // Pretend the last start stored `Health` as a plain `u32`
+        let app = location.app(setup);
+        let keyspace = app.world().resource::<KeyspaceWrapper>();
+        open_metadata_partition(keyspace)
+            .insert(format!("schema/{}", std::any::type_name::<Health>()), [0])
+            .unwrap();
+        raw_partition::<Health>(app.world())
+            .insert(
+                entity.index().to_be_bytes(),
+                Bincode::serialize(&7u32).unwrap(),
+            )
+            .unwrap();
+    }
+
+    let mut app = location.app(setup);
+    let health: Vec<_> = app
+        .world_mut()
+        .query::<&Health>()
+        .iter(app.world())
+        .collect();
+    assert_eq!(
+        health,
+        [&Health {
+            current: 7,
+            max: 100
+        }]
+    );
+    assert_eq!(app.world().resource::<Messages<SchemaChanged>>().len(), 1);
+    assert!(messages::<DatabaseError>(&app).is_empty());
+
+    // The stored hash is up to date, the migrated record isn't migrated again
+    drop(app);
+    let app = location.app(setup);
+    assert_eq!(app.world().resource::<Messages<SchemaChanged>>().len(), 0);
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(reader.iter_persisted::<Health>().count(), 1);
+}
+
+/// Bincode, refusing to encode anything over 16 bytes
+struct Small;
+
+impl DatabaseFormat for Small {
+    fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, FormatError> {
+        let bytes = Bincode::serialize(value)?;
+        match bytes.len() <= 16 {
+            true => Ok(bytes),
+            false => Err("record doesn't fit in 16 bytes".into()),
+        }
+    }
+
+    fn deserialize<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T, FormatError> {
+        Bincode::deserialize(bytes)
+    }
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Label(String);
+
+#[test]
+fn snapshots_report_format_errors() {
+    let mut app = App::new();
+    app.add_plugins(DatabasePlugin)
+        .add_database_mapping::<Score>()
+        .add_database_mapping::<Label>()
+        .set_database_format::<Label, Small>();
+
+    let entity = app
+        .world_mut()
+        .spawn((Score(1), Label("far too long".into())))
+        .id();
+    assert!(matches!(
+        snapshot_entity(app.world(), entity),
+        Err(DatabaseError::Serialization { .. })
+    ));
+    assert!(export_entity(app.world(), entity).is_err());
+
+    let despawned = app.world_mut().spawn_empty().id();
+    app.world_mut().despawn(despawned);
+    assert!(matches!(snapshot_entity(app.world(), despawned), Ok(None)));
+}
+
+#[test]
+fn malformed_blobs_spawn_nothing() {
+    let mut app = App::new();
+    app.add_plugins(DatabasePlugin)
+        .add_database_mapping::<Score>()
+        .add_database_mapping::<Label>();
+
+    let blob = EntityBlob {
+        components: vec![
+            (std::any::type_name::<Score>().to_string(), vec![1, 0, 0, 0]),
+            (std::any::type_name::<Label>().to_string(), vec![1]),
+        ],
+    };
+    let entities = app.world().entities().len();
+    assert!(spawn_from_blob(app.world_mut(), &blob).is_err());
+    assert_eq!(app.world().entities().len(), entities);
+}
+
+#[test]
+fn failed_writes_are_not_remembered_as_persisted() {
+    let location = TestLocation::new("significance_failed_write");
+    let mut app = location.app(|app| {
+        app.add_database_mapping::<Label>()
+            .set_database_format::<Label, Small>()
+            .set_database_significance::<Label>(|old, new| old.0.len().abs_diff(new.0.len()) >= 3);
+    });
+    let entity = app.world_mut().spawn(Label("a".into())).id();
+    app.update();
+
+    // Too long to encode, the write fails
+    app.world_mut().get_mut::<Label>(entity).unwrap().0 = "a".repeat(9);
+    app.update();
+    // Close to the failed value, but far from the persisted one
+    app.world_mut().get_mut::<Label>(entity).unwrap().0 = "a".repeat(8);
+    app.update();
+
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(
+        reader.get_persisted::<Label>(entity),
+        Some(Label("a".repeat(8)))
+    );
+}
+
+#[test]
+fn ttls_too_long_for_nanoseconds_never_run_out() {
+    let location = TestLocation::new("ttl_long");
+    // Exactly 2^64 nanoseconds
+    let setup = |app: &mut App| {
+        app.add_database_mapping_ttl::<Score>(Duration::new(18_446_744_073, 709_551_616));
+    };
+    {
+        let mut app = location.app(setup);
+        app.world_mut().spawn(Score(1));
+        app.update();
+        app.update();
+        assert_eq!(
+            app.world()
+                .resource::<DatabaseReader>()
+                .iter_persisted::<Score>()
+                .count(),
+            1
+        );
+    }
+
+    let mut app = location.app(setup);
+    assert_eq!(
+        app.world_mut().query::<&Score>().iter(app.world()).count(),
+        1
+    );
+}
+
+#[test]
+fn immediate_writes_expire_with_the_ttl() {
+    let location = TestLocation::new("ttl_save_now");
+    let mut app = location.app(|app| {
+        app.add_database_mapping_ttl::<Score>(Duration::from_millis(100));
+    });
+    let entity = app.world_mut().spawn_empty().id();
+    save_type_now(app.world_mut(), entity, &Score(1));
+    app.update();
+
+    std::thread::sleep(Duration::from_millis(200));
+    app.update();
+    app.update();
+
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(reader.get_persisted::<Score>(entity), None);
+}
+
+#[test]
+fn time_budget_is_shared_between_types() {
+    let location = TestLocation::new("time_budget");
+    let mut app = location.app(|app| {
+        app.insert_resource(SerializationTimeBudget(Duration::ZERO))
+            .add_database_mapping::<Score>()
+            .add_database_mapping::<Label>();
+    });
+    app.world_mut().spawn_batch((0..3).map(Score));
+    app.world_mut()
+        .spawn_batch((0..3).map(|index| Label(index.to_string())));
+
+    let reader = app.world().resource::<DatabaseReader>().clone();
+    for saved in 1..=3 {
+        app.update();
+        assert_eq!(reader.iter_persisted::<Score>().count(), saved);
+        assert_eq!(reader.iter_persisted::<Label>().count(), saved);
+    }
+}
+
+#[test]
+fn creation_order_survives_reused_indices() {
+    let location = TestLocation::new("creation_order");
+    let setup = |app: &mut App| {
+        app.insert_resource(DatabaseCreationOrder)
+            .add_database_mapping::<Score>();
+    };
+    {
+        let mut app = location.app(setup);
+        let placeholder = app.world_mut().spawn_empty().id();
+        let first = app.world_mut().spawn(Score(1)).id();
+        app.update();
+
+        // Reuses the placeholder's index, lower than the first entity's
+        app.world_mut().despawn(placeholder);
+        let second = app.world_mut().spawn(Score(2)).id();
+        assert!(second.index() < first.index());
+        app.update();
+    }
+
+    let mut app = location.app(setup);
+    let mut loaded: Vec<(Entity, u32)> = app
+        .world_mut()
+        .query::<(Entity, &Score)>()
+        .iter(app.world())
+        .map(|(entity, score)| (entity, score.0))
+        .collect();
+    loaded.sort_by_key(|(entity, _)| entity.index());
+    assert_eq!(
+        loaded.iter().map(|(_, score)| *score).collect::<Vec<_>>(),
+        [1, 2]
+    );
+}
+
+/// Clamps volumes, older versions allowed values above 1.0
+fn clamp<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    Ok(f32::deserialize(deserializer)?.clamp(0.0, 1.0))
+}
+
+#[derive(Component, Serialize, Deserialize)]
+struct Volume(#[serde(deserialize_with = "clamp")] f32);
+
+/// Bytes of the only record of `T`
+fn only_record<T: Component>(app: &App) -> Vec<u8> {
+    let (_, stored) = raw_partition::<T>(app.world())
+        .iter()
+        .next()
+        .unwrap()
+        .unwrap();
+    stored.to_vec()
+}
+
+#[test]
+fn migrated_records_are_resaved() {
+    let location = TestLocation::new("resave");
+    let setup = |app: &mut App| {
+        app.insert_resource(DatabaseResaveMigrated)
+            .add_database_mapping::<Volume>();
+    };
+    {
+        let mut app = location.app(setup);
+        app.world_mut().spawn(Volume(1.5));
+        app.update();
+        assert_eq!(only_record::<Volume>(&app), 1.5f32.to_le_bytes());
+    }
+
+    let app = location.app(setup);
+    assert_eq!(only_record::<Volume>(&app), 1.0f32.to_le_bytes());
+}
+
+#[derive(Component, Serialize, Deserialize)]
+struct Inventory(HashMap<u32, u32>);
+
+#[test]
+fn non_canonical_records_are_not_resaved() {
+    let location = TestLocation::new("resave_hashmap");
+    let setup = |app: &mut App| {
+        app.insert_resource(DatabaseResaveMigrated)
+            .add_database_mapping::<Inventory>();
+    };
+    let first = {
+        let mut app = location.app(setup);
+        app.world_mut()
+            .spawn(Inventory((0..32).map(|item| (item, item)).collect()));
+        app.update();
+        only_record::<Inventory>(&app)
+    };
+
+    for _ in 0..3 {
+        assert_eq!(only_record::<Inventory>(&location.app(setup)), first);
+    }
+}
+
+#[test]
+fn version_mismatch_policies() {
+    let location = TestLocation::new("version_mismatch");
+    let app = |policy: VersionMismatchPolicy| {
+        location.app(|app| {
+            app.insert_resource(policy);
+        })
+    };
+    {
+        // Pretend a future version of the crate wrote the database
+        let app = app(VersionMismatchPolicy::Refuse);
+        let keyspace = app.world().resource::<KeyspaceWrapper>();
+        let metadata = keyspace
+            .open_partition("metadata", PartitionCreateOptions::default())
+            .unwrap();
+        metadata
+            .insert("layout_version", 2u16.to_be_bytes())
+            .unwrap();
+    }
+
+    {
+        let app = app(VersionMismatchPolicy::LoadBestEffort);
+        let mismatches = messages::<DatabaseVersionMismatch>(&app);
+        assert_eq!(
+            mismatches
+                .iter()
+                .map(|mismatch| (mismatch.found, mismatch.expected))
+                .collect::<Vec<_>>(),
+            [(2, DATABASE_LAYOUT_VERSION)]
+        );
+    }
+
+    // A successful migration marks the database as current
+    app(VersionMismatchPolicy::Migrate(|_keyspace, found| {
+        assert_eq!(found, 2);
+        Ok(())
+    }));
+    app(VersionMismatchPolicy::Refuse);
+}
+
+#[test]
+fn load_mapper_forgets_despawned_entities() {
+    let location = TestLocation::new("mapper");
+    let setup = |app: &mut App| {
+        app.add_database_mapping::<Score>();
+    };
+    {
+        let mut app = location.app(setup);
+        app.world_mut().spawn_batch((0..100).map(Score));
+        app.update();
+    }
+
+    let mut app = location.app(setup);
+    assert_eq!(app.world().resource::<DatabaseLoadMapper>().len(), 100);
+
+    // Churn through the loaded entities, replacing each with a fresh one
+    let loaded: Vec<Entity> = app
+        .world()
+        .resource::<DatabaseLoadMapper>()
+        .iter()
+        .map(|(_, live)| live)
+        .collect();
+    for entity in loaded {
+        app.world_mut().despawn(entity);
+        app.world_mut().spawn(Score(0));
+        app.update();
+    }
+    assert!(app.world().resource::<DatabaseLoadMapper>().is_empty());
+}
+
+#[test]
+fn ignoring_a_loaded_entity_deletes_its_stored_record() {
+    let location = TestLocation::new("ignore_loaded");
+    let setup = |app: &mut App| {
+        app.insert_resource(DatabaseIgnorePolicy::DeleteRecords)
+            .add_database_mapping::<Score>();
+    };
+    {
+        let mut app = location.app(setup);
+        // Stored under a higher index than it's loaded into on the next start
+        app.world_mut().spawn_batch((0..3).map(|_| ()));
+        app.world_mut().spawn(Score(2));
+        app.update();
+    }
+
+    {
+        let mut app = location.app(setup);
+        let loaded = app
+            .world_mut()
+            .query_filtered::<Entity, With<Score>>()
+            .single(app.world())
+            .unwrap();
+        app.world_mut().entity_mut(loaded).insert(DatabaseIgnore);
+        app.update();
+    }
+
+    let mut app = location.app(setup);
+    assert_eq!(
+        app.world_mut().query::<&Score>().iter(app.world()).count(),
+        0
+    );
+}
+
+#[test]
+fn format_errors_keep_the_previous_record() {
+    let location = TestLocation::new("format_error");
+    let mut app = location.app(|app| {
+        app.add_database_mapping::<Label>()
+            .set_database_format::<Label, Small>();
+    });
+    let entity = app.world_mut().spawn(Label("Al".into())).id();
+    app.update();
+    app.world_mut().get_mut::<Label>(entity).unwrap().0 = "Bartholomew".into();
+    app.update();
+
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(
+        reader.get_persisted::<Label>(entity),
+        Some(Label("Al".into()))
+    );
+    assert!(matches!(
+        messages::<DatabaseError>(&app)[..],
+        [DatabaseError::Serialization { .. }]
+    ));
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Ledger {
+    total: u128,
+    delta: i128,
+    history: [u64; 32],
+    samples: Vec<i128>,
+}
+
+#[test]
+fn bincode_stores_wide_integers_losslessly() {
+    let location = TestLocation::new("bincode_wide");
+    let mut app = location.app(|app| {
+        app.add_database_mapping::<Ledger>();
+    });
+    let ledger = Ledger {
+        total: u128::MAX,
+        delta: i128::MIN,
+        history: [u64::MAX; 32],
+        samples: vec![i128::MAX, -1, 0, i128::MIN],
+    };
+    let entity = app.world_mut().spawn(ledger.clone()).id();
+    app.update();
+
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(reader.get_persisted::<Ledger>(entity), Some(ledger));
+}
+
+#[test]
+fn self_describing_records_load_with_new_fields() {
+    #[derive(Serialize)]
+    struct OldWallet {
+        gold: u32,
+    }
+    #[derive(Component, Serialize, Deserialize)]
+    struct Wallet {
+        gold: u32,
+        #[serde(default)]
+        gems: u32,
+    }
+
+    let location = TestLocation::new("self_describing");
+    let setup = |app: &mut App| {
+        app.add_database_mapping::<Wallet>()
+            .set_database_format::<Wallet, SelfDescribing>();
+    };
+    {
+        let app = location.app(setup);
+        let old = SelfDescribing::serialize(&OldWallet { gold: 5 }).unwrap();
+        raw_partition::<Wallet>(app.world())
+            .insert(0u32.to_be_bytes(), old)
+            .unwrap();
+    }
+
+    let mut app = location.app(setup);
+    let wallet = app
+        .world_mut()
+        .query::<&Wallet>()
+        .single(app.world())
+        .unwrap();
+    assert_eq!((wallet.gold, wallet.gems), (5, 0));
+}
+
+#[derive(Component, Serialize, Deserialize)]
+struct Player;
+
+#[test]
+fn defaults_are_seeded_and_saved() {
+    let location = TestLocation::new("with_default");
+    let mut app = location.app(|app| {
+        app.add_database_mapping::<Player>()
+            .add_database_mapping_with_default::<_, With<Player>>(|| Score(100));
+    });
+    let player = app.world_mut().spawn(Player).id();
+    let rich_player = app.world_mut().spawn((Player, Score(500))).id();
+    let chest = app.world_mut().spawn_empty().id();
+    app.update();
+
+    assert_eq!(app.world().get::<Score>(player), Some(&Score(100)));
+    assert_eq!(app.world().get::<Score>(rich_player), Some(&Score(500)));
+    assert!(app.world().get::<Score>(chest).is_none());
+
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(reader.get_persisted::<Score>(player), Some(Score(100)));
+    assert_eq!(reader.iter_persisted::<Score>().count(), 2);
+}
+
+#[test]
+fn capped_types_evict_the_oldest_records() {
+    let location = TestLocation::new("capped");
+    let mut app = location.app(|app| {
+        app.add_database_mapping_capped::<Score>(2);
+    });
+    let scores: Vec<Entity> = (0..3)
+        .map(|score| {
+            let entity = app.world_mut().spawn(Score(score)).id();
+            app.update();
+            entity
+        })
+        .collect();
+    app.update();
+
+    assert!(app.world().get::<Score>(scores[0]).is_none());
+    let reader = app.world().resource::<DatabaseReader>();
+    let mut persisted: Vec<u32> = reader
+        .iter_persisted::<Score>()
+        .map(|(_, score)| score.0)
+        .collect();
+    persisted.sort();
+    assert_eq!(persisted, [1, 2]);
+}
+
+#[test]
+fn expired_records_are_not_loaded() {
+    let location = TestLocation::new("ttl_load");
+    let setup = |app: &mut App| {
+        app.add_database_mapping_ttl::<Score>(Duration::from_millis(100));
+    };
+    {
+        let mut app = location.app(setup);
+        app.world_mut().spawn(Score(1));
+        app.update();
+        std::thread::sleep(Duration::from_millis(200));
+
+        // Staged loads skip expired records too
+        let reader = app.world().resource::<DatabaseReader>().clone();
+        let mut staging = reader.load_staging_world();
+        assert_eq!(staging.query::<&Score>().iter(&staging).count(), 0);
+    }
+
+    // Expired while the app wasn't running
+    let mut app = location.app(setup);
+    assert_eq!(
+        app.world_mut().query::<&Score>().iter(app.world()).count(),
+        0
+    );
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(reader.iter_persisted::<Score>().count(), 0);
+}
+
+#[test]
+fn expired_records_are_swept_at_runtime() {
+    let location = TestLocation::new("ttl_sweep");
+    let mut app = location.app(|app| {
+        app.add_database_mapping_ttl::<Score>(Duration::from_millis(100));
+    });
+    let entity = app.world_mut().spawn(Score(2)).id();
+    app.update();
+    std::thread::sleep(Duration::from_millis(200));
+    app.update();
+    app.update();
+
+    assert!(app.world().get::<Score>(entity).is_none());
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(reader.iter_persisted::<Score>().count(), 0);
+}
+
+#[test]
+fn lazy_records_load_on_demand() {
+    let location = TestLocation::new("lazy");
+    let setup = |app: &mut App| {
+        app.add_database_mapping_lazy::<Terrain>();
+    };
+    {
+        let mut app = location.app(setup);
+        app.world_mut().spawn(Terrain(vec![1, 2, 3]));
+        app.update();
+    }
+
+    let mut app = location.app(setup);
+    let entity = app
+        .world_mut()
+        .query_filtered::<Entity, With<DatabaseLazy<Terrain>>>()
+        .single(app.world())
+        .unwrap();
+    assert!(app.world().get::<Terrain>(entity).is_none());
+
+    app.world_mut()
+        .commands()
+        .entity(entity)
+        .queue(load_lazy_component::<Terrain>);
+    app.world_mut().flush();
+    assert_eq!(
+        app.world().get::<Terrain>(entity),
+        Some(&Terrain(vec![1, 2, 3]))
+    );
+}
+
+/// How many labels existed whenever a score was added
+#[derive(Resource, Default)]
+struct LabelsSeen(Vec<usize>);
+
+#[test]
+fn load_after_orders_the_loads() {
+    let location = TestLocation::new("load_after");
+    let mut app = location.app(|app| {
+        app.add_database_mapping::<Label>()
+            .add_database_mapping::<Score>()
+            .set_database_load_after::<Score, Label>()
+            .init_resource::<LabelsSeen>()
+            .add_observer(
+                |_: On<Add, Score>, labels: Query<&Label>, mut seen: ResMut<LabelsSeen>| {
+                    seen.0.push(labels.iter().count());
+                },
+            );
+    });
+    app.world_mut().spawn(Label("Red".into()));
+    app.world_mut().spawn(Score(0));
+    app.update();
+
+    // Labels are back before any score is loaded
+    reload_world(app.world_mut());
+    assert_eq!(app.world().resource::<LabelsSeen>().0.last(), Some(&1));
+}
+
+#[test]
+fn names_are_persisted() {
+    let location = TestLocation::new("names");
+    let setup = |app: &mut App| {
+        app.add_name_persistence();
+    };
+    {
+        let mut app = location.app(setup);
+        app.world_mut().spawn(Name::new("Player One"));
+        app.update();
+    }
+
+    let mut app = location.app(setup);
+    let name = app
+        .world_mut()
+        .query::<&Name>()
+        .single(app.world())
+        .unwrap();
+    assert_eq!(name, &Name::new("Player One"));
+}
+
+#[test]
+fn relationships_are_remapped_on_load() {
+    let location = TestLocation::new("relationship");
+    let setup = |app: &mut App| {
+        app.add_database_mapping::<Score>()
+            .add_database_relationship::<ChildOf>();
+    };
+    {
+        let mut app = location.app(setup);
+        let parent = app.world_mut().spawn(Score(1)).id();
+        app.world_mut().spawn((Score(2), ChildOf(parent)));
+        app.update();
+    }
+
+    let mut app = location.app(setup);
+    let (child_score, parent) = app
+        .world_mut()
+        .query::<(&Score, &ChildOf)>()
+        .single(app.world())
+        .map(|(score, child_of)| (score.0, child_of.parent()))
+        .unwrap();
+    assert_eq!(child_score, 2);
+    assert_eq!(app.world().get::<Score>(parent), Some(&Score(1)));
+    assert_eq!(app.world().get::<Children>(parent).unwrap().len(), 1);
+
+    // Staged loads point at the staged parent, not at whatever has its old id
+    let reader = app.world().resource::<DatabaseReader>().clone();
+    let loaded = apply_staging_world(app.world_mut(), reader.load_staging_world());
+    let staged_parent = loaded
+        .iter()
+        .find_map(|&entity| app.world().get::<ChildOf>(entity))
+        .unwrap()
+        .parent();
+    assert!(loaded.contains(&staged_parent));
+    assert_eq!(app.world().get::<Score>(staged_parent), Some(&Score(1)));
+}
+
+#[derive(Component, Default)]
+struct Velocity;
+
+#[test]
+fn loaded_entities_get_the_spawn_bundle() {
+    let location = TestLocation::new("spawn_bundle");
+    let setup = |app: &mut App| {
+        app.add_database_mapping::<Score>()
+            .set_load_spawn_bundle(Velocity::default);
+    };
+    {
+        let mut app = location.app(setup);
+        app.world_mut().spawn(Score(3));
+        app.update();
+        assert_eq!(
+            app.world_mut()
+                .query::<&Velocity>()
+                .iter(app.world())
+                .count(),
+            0
+        );
+    }
+
+    let mut app = location.app(setup);
+    let loaded = app
+        .world_mut()
+        .query_filtered::<(), (With<Score>, With<Velocity>)>()
+        .iter(app.world())
+        .count();
+    assert_eq!(loaded, 1);
+
+    // Entities moved in from a staged load get the bundle as well
+    let reader = app.world().resource::<DatabaseReader>().clone();
+    let loaded = apply_staging_world(app.world_mut(), reader.load_staging_world());
+    assert!(app.world().get::<Velocity>(loaded[0]).is_some());
+}
+
+#[derive(Component, bevy_reflect::Reflect, Serialize, Deserialize)]
+struct HealthV1(u32);
+
+#[test]
+fn schema_changes_are_detected_once() {
+    let location = TestLocation::new("schema_check");
+    let health_v1 = std::any::type_name::<HealthV1>();
+    {
+        let app = location.app(|app| {
+            app.add_database_mapping::<HealthV1>()
+                .add_database_schema_check::<HealthV1>();
+        });
+        assert!(messages::<SchemaChanged>(&app).is_empty());
+    }
+
+    // Pretend `HealthV1` gained fields by checking a different type against its hash
+    let setup = |app: &mut App| {
+        app.add_database_mapping::<Health>()
+            .add_database_schema_check_as::<Health>(health_v1);
+    };
+    let app = location.app(setup);
+    let changes = messages::<SchemaChanged>(&app);
+    assert_eq!(
+        changes
+            .iter()
+            .map(|change| change.type_name)
+            .collect::<Vec<_>>(),
+        [health_v1]
+    );
+    drop(app);
+    assert!(messages::<SchemaChanged>(&location.app(setup)).is_empty());
+}
+
+#[test]
+fn immediate_writes_win_over_pending_batched_ones() {
+    let location = TestLocation::new("save_now_batch");
+    let mut app = location.app(|app| {
+        app.insert_resource(DatabaseBatchWindow(Duration::from_secs(60)))
+            .add_database_mapping::<Score>();
+    });
+
+    // Leaves a batched write of the record pending
+    let entity = app.world_mut().spawn(Score(100)).id();
+    app.update();
+
+    let reader = app.world().resource::<DatabaseReader>().clone();
+    for value in 0..10 {
+        save_type_now(app.world_mut(), entity, &Score(value));
+        // Reads immediately observe the preceding write
+        assert_eq!(reader.get_persisted::<Score>(entity), Some(Score(value)));
+    }
+
+    flush_database_writes(app.world());
+    assert_eq!(reader.get_persisted::<Score>(entity), Some(Score(9)));
+}
+
+#[test]
+fn manual_ticks_only_save_write_once_types_when_added() {
+    let location = TestLocation::new("manual_on_add");
+    let mut app = App::new();
+    app.insert_resource(DatabaseLocation(location.0.to_string_lossy().into_owned()))
+        .add_plugins(DatabasePlugin)
+        .add_database_mapping::<Score>()
+        .add_database_mapping_on_add::<Label>();
+
+    let world = app.world_mut();
+    open_database(world);
+    load_type::<Score>(world);
+    load_type::<Label>(world);
+    finish_database_tick(world);
+
+    let entity = world.spawn((Score(0), Label(String::new()))).id();
+    for _ in 0..3 {
+        world.get_mut::<Score>(entity).unwrap().0 += 1;
+        world.get_mut::<Label>(entity).unwrap().0.push('a');
+        save_type::<Score>(world);
+        save_type::<Label>(world);
+        finish_database_tick(world);
+        world.clear_trackers();
+    }
+
+    let reader = world.resource::<DatabaseReader>();
+    assert_eq!(reader.get_persisted::<Score>(entity), Some(Score(3)));
+    assert_eq!(
+        reader.get_persisted::<Label>(entity),
+        Some(Label("a".into()))
+    );
+}
+
+#[test]
+fn reloading_discards_unsaved_changes_but_keeps_ignored_entities() {
+    let location = TestLocation::new("reload");
+    let mut app = location.app(|app| {
+        app.add_database_mapping::<Score>();
+    });
+    let entity = app.world_mut().spawn(Score(1)).id();
+    app.update();
+
+    app.world_mut().get_mut::<Score>(entity).unwrap().0 = 99;
+    app.world_mut().spawn(Score(2));
+    let camera = app.world_mut().spawn(DatabaseIgnore).id();
+
+    reload_world(app.world_mut());
+    app.update();
+
+    let scores: Vec<_> = app
+        .world_mut()
+        .query::<&Score>()
+        .iter(app.world())
+        .cloned()
+        .collect();
+    assert_eq!(scores, [Score(1)]);
+    assert!(app.world().get_entity(camera).is_ok());
+
+    // The despawns didn't delete the reloaded record
+    app.update();
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(reader.iter_persisted::<Score>().count(), 1);
+}
+
+#[test]
+fn exports_import_into_another_database() {
+    let setup = |app: &mut App| {
+        app.add_database_mapping::<Score>();
+    };
+    let source = TestLocation::new("export");
+    let mut export = Vec::new();
+    {
+        let mut app = source.app(setup);
+        app.world_mut().spawn_batch((0..100).map(Score));
+        app.update();
+        export_database(app.world(), &mut export).unwrap();
+    }
+
+    let target = TestLocation::new("import");
+    let app = target.app(setup);
+    assert_eq!(
+        import_database(app.world(), export.as_slice()).unwrap(),
+        100
+    );
+    let reader = app.world().resource::<DatabaseReader>();
+    assert_eq!(reader.iter_persisted::<Score>().count(), 100);
+}
+
+#[test]
+fn merges_copy_new_records_and_resolve_conflicts() {
+    let setup = |app: &mut App| {
+        app.add_database_mapping::<Score>();
+    };
+    let friend = TestLocation::new("merge_friend");
+    {
+        let mut app = friend.app(setup);
+        app.world_mut().spawn(Score(1));
+        app.world_mut().spawn(Score(2));
+        app.update();
+    }
+
+    let location = TestLocation::new("merge");
+    let mut app = location.app(setup);
+    app.world_mut().spawn(Score(10));
+    app.update();
+
+    let missing = TestLocation::new("merge_missing");
+    assert!(merge_database(app.world(), &missing.0, MergeStrategy::KeepLocal, true).is_err());
+    assert!(!missing.0.exists());
+
+    let preview = merge_database(app.world(), &friend.0, MergeStrategy::Newest, true).unwrap();
+    assert_eq!((preview.added, preview.conflicts.len()), (1, 1));
+    assert_eq!(preview.untimed_types, [std::any::type_name::<Score>()]);
+
+    merge_database(app.world(), &friend.0, MergeStrategy::KeepLocal, false).unwrap();
+    let reader = app.world().resource::<DatabaseReader>();
+    let mut scores: Vec<u32> = reader
+        .iter_persisted::<Score>()
+        .map(|(_, score)| score.0)
+        .collect();
+    scores.sort();
+    assert_eq!(scores, [2, 10]);
+}